@@ -16,7 +16,7 @@ ga_generator::ga! {
     group VgaNoE4Quadvector  = VgaNoE4Vector ^ VgaNoE4Trivector;
     group VgaNoE4Pentavector = VgaNoE4Vector ^ VgaNoE4Quadvector;
 
-    group NoE4Rotor = Scalar + VgaNoE4Bivector + VgaNoE4Quadvector;
+    group #[derive(PartialEq, Serialize, Deserialize)] NoE4Rotor = Scalar + VgaNoE4Bivector + VgaNoE4Quadvector;
 
     fn rotor_no_e4_then(a: NoE4Rotor, b: NoE4Rotor) -> NoE4Rotor {
         return b * a;
@@ -152,7 +152,7 @@ ga_generator::ga! {
     group VgaQuadvector  = VgaVector ^ VgaTrivector;
     group VgaPentavector = VgaVector ^ VgaQuadvector;
 
-    group #[derive(Zeroable, Pod, Serialize, Deserialize)] #[repr(C)] Rotor = Scalar + VgaBivector + VgaQuadvector;
+    group #[derive(PartialEq, Zeroable, Pod, Serialize, Deserialize)] #[repr(C)] Rotor = Scalar + VgaBivector + VgaQuadvector;
 
     group RotorMagnitude = Scalar + VgaQuadvector;
     fn rotor_squared_magnitude(rotor: Rotor) -> RotorMagnitude {
@@ -450,6 +450,26 @@ impl NoE4Rotor {
             rotor_no_e4_w(self);
         Vector4 { x, y, z, w }
     }
+
+    /// The rotation `t` of the way along the shortest arc from `self` to `to`, turning at a
+    /// constant rate
+    ///
+    /// This is a 3d rotation, so it is the same as slerping the quaternion with the same components
+    pub fn slerp(self, to: Self, t: f32) -> Self {
+        let from = [self.s, self.e1e2, self.e1e3, self.e2e3];
+        let mut to = [to.s, to.e1e2, to.e1e3, to.e2e3];
+        // negating a rotor gives the same rotation, the other way around
+        if dot(from, to) < 0.0 {
+            to = to.map(|c| -c);
+        }
+        let [s, e1e2, e1e3, e2e3] = slerp_quaternion(from, to, t);
+        Self {
+            s,
+            e1e2,
+            e1e3,
+            e2e3,
+        }
+    }
 }
 
 impl Rotor {
@@ -602,6 +622,49 @@ impl Rotor {
         Vector4 { x, y, z, w }
     }
 
+    /// The rotor that rotates by `angles` in the xy, xz, xw, yz, yw, and zw planes in turn, each
+    /// applied with [`Rotor::then`] so that each plane is in the frame left by the ones before it
+    pub fn from_plane_angles(angles: [f32; 6]) -> Self {
+        let [xy, xz, xw, yz, yw, zw] = angles;
+        Self::rotate_xy(xy)
+            .then(Self::rotate_xz(xz))
+            .then(Self::rotate_xw(xw))
+            .then(Self::rotate_yz(yz))
+            .then(Self::rotate_yw(yw))
+            .then(Self::rotate_zw(zw))
+    }
+
+    /// The angles that [`Rotor::from_plane_angles`] turns back into this rotation
+    ///
+    /// The first three angles are read off where x ends up, which none of the later planes move,
+    /// then the next two off where y ends up once they are undone, and the last off z. The xy, yz,
+    /// and zw angles are in `-PI..=PI` and the rest in `-PI / 2..=PI / 2`, so the same rotation
+    /// always gives the same angles
+    pub fn plane_angles(self) -> [f32; 6] {
+        let x = self.x();
+        let xy = x.y.atan2(x.x);
+        let xz = x.z.atan2(x.x.hypot(x.y));
+        let xw = x.w.atan2(x.x.hypot(x.y).hypot(x.z));
+
+        let rest = Self::rotate_xy(xy)
+            .then(Self::rotate_xz(xz))
+            .then(Self::rotate_xw(xw))
+            .reverse()
+            .then(self);
+        let y = rest.y();
+        let yz = y.z.atan2(y.y);
+        let yw = y.w.atan2(y.y.hypot(y.z));
+
+        let rest = Self::rotate_yz(yz)
+            .then(Self::rotate_yw(yw))
+            .reverse()
+            .then(rest);
+        let z = rest.z();
+        let zw = z.w.atan2(z.z);
+
+        [xy, xz, xw, yz, yw, zw]
+    }
+
     #[inline]
     pub fn from_no_e4_rotor(rotor: NoE4Rotor) -> Self {
         let NoE4Rotor {
@@ -621,6 +684,77 @@ impl Rotor {
             e1e2e3e4: 0.0,
         }
     }
+
+    /// The rotation `t` of the way along the shortest geodesic from `self` to `to`
+    ///
+    /// The even subalgebra that rotors live in splits into two quaternion algebras, picked out by
+    /// multiplying by `(1 + e1e2e3e4) / 2` and `(1 - e1e2e3e4) / 2`, which are the left and
+    /// right isoclinic halves of the rotation. Slerping each half on its own moves both at a
+    /// constant rate, which is a geodesic of the whole rotation
+    pub fn slerp(self, to: Self, t: f32) -> Self {
+        let halves = |r: Self| {
+            (
+                [
+                    r.s + r.e1e2e3e4,
+                    r.e1e2 - r.e3e4,
+                    r.e1e3 + r.e2e4,
+                    r.e1e4 - r.e2e3,
+                ],
+                [
+                    r.s - r.e1e2e3e4,
+                    r.e1e2 + r.e3e4,
+                    r.e1e3 - r.e2e4,
+                    r.e1e4 + r.e2e3,
+                ],
+            )
+        };
+        let (from_left, from_right) = halves(self);
+        let (mut to_left, mut to_right) = halves(to);
+
+        // negating both halves gives the same rotation, which turns each half the other way around
+        // instead, so take whichever turns less in total, but negating only one half would be a
+        // different rotation
+        let angle = |a, b| dot(a, b).clamp(-1.0, 1.0).acos();
+        if angle(from_left, to_left) + angle(from_right, to_right) > std::f32::consts::PI {
+            to_left = to_left.map(|c| -c);
+            to_right = to_right.map(|c| -c);
+        }
+
+        let left = slerp_quaternion(from_left, to_left, t);
+        let right = slerp_quaternion(from_right, to_right, t);
+        Self {
+            s: (left[0] + right[0]) * 0.5,
+            e1e2: (left[1] + right[1]) * 0.5,
+            e1e3: (left[2] + right[2]) * 0.5,
+            e1e4: (left[3] + right[3]) * 0.5,
+            e2e3: (right[3] - left[3]) * 0.5,
+            e2e4: (left[2] - right[2]) * 0.5,
+            e3e4: (right[1] - left[1]) * 0.5,
+            e1e2e3e4: (left[0] - right[0]) * 0.5,
+        }
+    }
+}
+
+fn dot(a: [f32; 4], b: [f32; 4]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Slerps unit quaternions along the arc between them, which is the long way around if their dot
+/// product is negative, so the callers pick the sign of `to`
+fn slerp_quaternion(from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+    let dot = dot(from, to).clamp(-1.0, 1.0);
+
+    // too close together for the angle to be accurate, but then a straight line is close enough
+    let (from_weight, to_weight) = if dot > 0.9995 {
+        (1.0 - t, t)
+    } else {
+        let angle = dot.acos();
+        let sin = angle.sin();
+        (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+    };
+    let result: [f32; 4] = std::array::from_fn(|i| from[i] * from_weight + to[i] * to_weight);
+    let length = result.iter().map(|c| c * c).sum::<f32>().sqrt();
+    result.map(|c| c / length)
 }
 
 impl Transform {
@@ -0,0 +1,139 @@
+use crate::camera::{Camera, Movement};
+use math::{NoE4Rotor, Vector4};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Sub};
+
+/// Where the camera is at one point in a camera path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Seconds from the start of the path
+    pub time: f32,
+    pub position: Vector4<f32>,
+    pub base_rotation: NoE4Rotor,
+    pub xw_rotation: f32,
+    pub fov: f32,
+}
+
+impl Keyframe {
+    pub fn new(time: f32, camera: &Camera) -> Self {
+        Self {
+            time,
+            position: camera.position,
+            base_rotation: camera.base_rotation,
+            xw_rotation: camera.xw_rotation,
+            fov: camera.fov,
+        }
+    }
+
+    /// Moves `camera` to the keyframe, flying, because walking would pull it back onto the surface
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.position = self.position;
+        camera.base_rotation = self.base_rotation;
+        camera.xw_rotation = self.xw_rotation;
+        camera.fov = self.fov;
+        camera.movement = Movement::Fly;
+    }
+}
+
+/// The time of the last keyframe, which is when the path ends
+pub fn duration(keyframes: &[Keyframe]) -> f32 {
+    keyframes
+        .iter()
+        .map(|keyframe| keyframe.time)
+        .fold(0.0, f32::max)
+}
+
+/// Where the camera is at `time` along the path through `keyframes`, which can be in any order, or
+/// `None` if there are no keyframes
+///
+/// The position, xw rotation, and fov follow a Catmull-Rom spline through the keyframes, which
+/// passes through each of them without any sudden changes of velocity, and the base rotation turns
+/// at a constant rate between each pair of keyframes. Before the first keyframe the camera stays
+/// at it, and the same after the last
+pub fn sample(keyframes: &[Keyframe], time: f32) -> Option<Keyframe> {
+    let mut keyframes = keyframes.iter().collect::<Vec<_>>();
+    keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let first = *keyframes.first()?;
+    let last = *keyframes.last()?;
+    if time <= first.time {
+        return Some(Keyframe {
+            time,
+            ..first.clone()
+        });
+    }
+    if time >= last.time {
+        return Some(Keyframe {
+            time,
+            ..last.clone()
+        });
+    }
+
+    // `a.time <= time < b.time`, so the segment is never empty
+    let i = keyframes.partition_point(|keyframe| keyframe.time <= time) - 1;
+    let (a, b) = (keyframes[i], keyframes[i + 1]);
+    let before = keyframes[i.saturating_sub(1)];
+    let after = keyframes[(i + 2).min(keyframes.len() - 1)];
+
+    let t = (time - a.time) / (b.time - a.time);
+    let segment = [before, a, b, after];
+
+    Some(Keyframe {
+        time,
+        position: spline(segment, t, |keyframe| keyframe.position),
+        base_rotation: a.base_rotation.slerp(b.base_rotation, t),
+        xw_rotation: spline(segment, t, |keyframe| keyframe.xw_rotation),
+        fov: spline(segment, t, |keyframe| keyframe.fov),
+    })
+}
+
+/// `field` `t` of the way from the second to the third of `keyframes`, with the velocity at each
+/// end taken from the keyframes either side of it over the time between them
+fn spline<T>(keyframes: [&Keyframe; 4], t: f32, field: impl Fn(&Keyframe) -> T) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    let [before, a, b, after] = keyframes;
+    let length = b.time - a.time;
+    // in the units of `t` rather than seconds
+    let tangent = |from: &Keyframe, to: &Keyframe| {
+        (field(to) - field(from)) * (length / (to.time - from.time))
+    };
+    let (tangent_a, tangent_b) = (tangent(before, b), tangent(a, after));
+
+    let t2 = t * t;
+    let t3 = t2 * t;
+    field(a) * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + tangent_a * (t3 - 2.0 * t2 + t)
+        + field(b) * (-2.0 * t3 + 3.0 * t2)
+        + tangent_b * (t3 - t2)
+}
+
+/// Playing back a camera path
+#[derive(Debug, Default)]
+pub struct Timeline {
+    /// Seconds from the start of the path
+    pub time: f32,
+    pub playing: bool,
+    /// Start again from the beginning after the end, instead of stopping
+    pub looping: bool,
+}
+
+impl Timeline {
+    /// Moves the time on by `dt` while playing a path that takes `duration` seconds
+    pub fn update(&mut self, dt: f32, duration: f32) {
+        if !self.playing {
+            return;
+        }
+
+        self.time += dt;
+        if self.time >= duration {
+            if self.looping && duration > 0.0 {
+                self.time %= duration;
+            } else {
+                self.time = duration;
+                self.playing = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use math::Vector4;
+use raytracing::{camera::ViewMode, cpu, path_tracing, scene::Scene};
+use std::{path::PathBuf, process::ExitCode, str::FromStr};
+
+const USAGE: &str = "\
+Renders a frame on the CPU and writes it to a png, without opening a window
+
+Usage: render [OPTIONS]
+
+Options:
+  --scene <PATH>             Scene file to render, instead of the default scene
+  --width <PIXELS>           Width of the image [default: 1920]
+  --height <PIXELS>          Height of the image [default: 1080]
+  -o, --output <PATH>        Where to write the png [default: render.png]
+  --position <X,Y,Z,W>       Override the position of the camera
+  --xw-rotation <DEGREES>    Override the rotation of the camera in the xw plane
+  --fov <DEGREES>            Override the vertical field of view of the camera
+  --view <MODE>              Override the view mode of the camera [possible values: embedded, surface]
+  --samples <COUNT>          Path trace the image with this many samples per pixel, instead of lighting it directly
+  --max-bounces <COUNT>      How many times path traced rays can bounce [default: 4]
+  -h, --help                 Print this message
+";
+
+struct Args {
+    scene: Option<PathBuf>,
+    width: u32,
+    height: u32,
+    output: PathBuf,
+    position: Option<Vector4<f32>>,
+    xw_rotation: Option<f32>,
+    fov: Option<f32>,
+    view_mode: Option<ViewMode>,
+    samples: Option<u32>,
+    max_bounces: u32,
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{flag}`"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for `{flag}`"))
+}
+
+fn parse_view_mode(flag: &str, value: Option<String>) -> Result<ViewMode, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{flag}`"))?;
+    match value.as_str() {
+        "embedded" => Ok(ViewMode::Embedded),
+        "surface" => Ok(ViewMode::Surface),
+        _ => Err(format!("invalid value `{value}` for `{flag}`")),
+    }
+}
+
+fn parse_vector4(flag: &str, value: Option<String>) -> Result<Vector4<f32>, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{flag}`"))?;
+    let components = value
+        .split(',')
+        .map(|component| component.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid value `{value}` for `{flag}`"))?;
+    let &[x, y, z, w] = components.as_slice() else {
+        return Err(format!(
+            "expected 4 comma separated components for `{flag}`, got `{value}`"
+        ));
+    };
+    Ok(Vector4 { x, y, z, w })
+}
+
+impl Args {
+    /// Returns `Ok(None)` if help was requested
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut parsed = Self {
+            scene: None,
+            width: 1920,
+            height: 1080,
+            output: PathBuf::from("render.png"),
+            position: None,
+            xw_rotation: None,
+            fov: None,
+            view_mode: None,
+            samples: None,
+            max_bounces: path_tracing::DEFAULT_MAX_BOUNCES,
+        };
+
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--scene" => parsed.scene = Some(parse_value(&flag, args.next())?),
+                "--width" => parsed.width = parse_value(&flag, args.next())?,
+                "--height" => parsed.height = parse_value(&flag, args.next())?,
+                "-o" | "--output" => parsed.output = parse_value(&flag, args.next())?,
+                "--position" => parsed.position = Some(parse_vector4(&flag, args.next())?),
+                "--xw-rotation" => {
+                    parsed.xw_rotation = Some(parse_value::<f32>(&flag, args.next())?.to_radians());
+                }
+                "--fov" => parsed.fov = Some(parse_value::<f32>(&flag, args.next())?.to_radians()),
+                "--view" => parsed.view_mode = Some(parse_view_mode(&flag, args.next())?),
+                "--samples" => parsed.samples = Some(parse_value(&flag, args.next())?),
+                "--max-bounces" => parsed.max_bounces = parse_value(&flag, args.next())?,
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(format!("unknown argument `{flag}`")),
+            }
+        }
+
+        if parsed.width == 0 || parsed.height == 0 {
+            return Err("the width and height must be greater than 0".into());
+        }
+        if parsed.samples == Some(0) {
+            return Err("the number of samples must be greater than 0".into());
+        }
+
+        Ok(Some(parsed))
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut scene = match &args.scene {
+        Some(path) => match Scene::load(path) {
+            Ok(scene) => scene,
+            Err(error) => {
+                eprintln!("error: could not load {}: {error}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Scene::default(),
+    };
+
+    let camera = &mut scene.camera;
+    if let Some(position) = args.position {
+        camera.position = position;
+    }
+    if let Some(xw_rotation) = args.xw_rotation {
+        camera.xw_rotation = xw_rotation;
+    }
+    if let Some(fov) = args.fov {
+        camera.fov = fov.clamp(0.0, 179f32.to_radians());
+    }
+    if let Some(view_mode) = args.view_mode {
+        camera.view_mode = view_mode;
+    }
+
+    scene.project_spheres();
+
+    let image = match args.samples {
+        Some(samples) => {
+            cpu::render_path_traced(&scene, args.width, args.height, samples, args.max_bounces)
+        }
+        None => cpu::render(&scene, args.width, args.height),
+    };
+    if let Err(error) = image.write_png(&args.output) {
+        eprintln!("error: could not write {}: {error}", args.output.display());
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
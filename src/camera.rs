@@ -1,34 +1,153 @@
+use crate::sdf;
 use bytemuck::NoUninit;
 use eframe::egui;
 use math::{NoE4Rotor, Rotor, Transform, Vector4};
-use std::f32::consts::TAU;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
 
+/// How rays leave the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ViewMode {
+    /// Straight rays through 4d space, which show the surface from outside
+    #[default]
+    Embedded,
+    /// Rays that start on the surface and follow it, which show what someone living in the surface would see
+    Surface,
+}
+
+/// How the camera moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Movement {
+    /// Flying freely through 4d space
+    #[default]
+    Fly,
+    /// Walking on the surface at the eye height, with ana along the surface normal
+    Walk,
+}
+
+/// A saved camera pose that can be returned to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub position: Vector4<f32>,
+    pub base_rotation: NoE4Rotor,
+    pub xw_rotation: f32,
+    pub walk_rotation: Rotor,
+    pub movement: Movement,
+    pub fov: f32,
+}
+
+impl Bookmark {
+    pub fn new(name: String, camera: &Camera) -> Self {
+        Self {
+            name,
+            position: camera.position,
+            base_rotation: camera.base_rotation,
+            xw_rotation: camera.xw_rotation,
+            walk_rotation: camera.walk_rotation,
+            movement: camera.movement,
+            fov: camera.fov,
+        }
+    }
+
+    /// Moves `camera` back to where it was when the bookmark was made
+    pub fn go_to(&self, camera: &mut Camera) {
+        camera.position = self.position;
+        camera.base_rotation = self.base_rotation;
+        camera.xw_rotation = self.xw_rotation;
+        camera.walk_rotation = self.walk_rotation;
+        camera.movement = self.movement;
+        camera.fov = self.fov;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Camera {
     pub position: Vector4<f32>,
     pub base_rotation: NoE4Rotor,
     pub xw_rotation: f32,
+    /// Used instead of `base_rotation` while walking, and kept with its w axis along the surface normal
+    pub walk_rotation: Rotor,
 
     pub fov: f32,
+    pub view_mode: ViewMode,
+    /// How many rays are traced through each pixel every frame
+    pub samples_per_pixel: u32,
+    /// Whether to keep averaging frames together while nothing changes
+    pub accumulate: bool,
+
+    pub movement: Movement,
+    /// Distance from the surface while walking
+    pub eye_height: f32,
+    /// Whether to stop at `min_distance` from the surface while flying, instead of passing through it
+    pub collision: bool,
+    pub min_distance: f32,
 
     pub move_speed: f32,
     pub rotate_speed: f32,
 }
 
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(Vector4 {
+            x: -3.0,
+            y: 0.0,
+            z: 0.0,
+            w: 2.0,
+        })
+    }
+}
+
 impl Camera {
     pub fn new(position: Vector4<f32>) -> Self {
         Self {
             position,
             base_rotation: NoE4Rotor::identity(),
             xw_rotation: 0.0,
+            walk_rotation: Rotor::identity(),
 
             fov: TAU * 0.25,
+            view_mode: ViewMode::Embedded,
+            samples_per_pixel: 1,
+            accumulate: false,
+
+            movement: Movement::Fly,
+            eye_height: 1.0,
+            collision: false,
+            min_distance: 0.2,
 
             move_speed: 5.0,
             rotate_speed: TAU * 0.5,
         }
     }
 
-    pub fn update(&mut self, ctx: &egui::Context, ts: f32) {
+    /// `scene_sdf` is the surface to walk on or collide with, if there is one
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        ts: f32,
+        scene_sdf: Option<impl Fn(Vector4<f32>) -> f32>,
+    ) {
+        match self.movement {
+            Movement::Fly => {
+                let previous_position = self.position;
+                self.fly(ctx, ts);
+                if self.collision
+                    && let Some(scene_sdf) = scene_sdf
+                {
+                    self.collide(previous_position, scene_sdf);
+                }
+            }
+            Movement::Walk => {
+                self.walk(ctx, ts);
+                if let Some(scene_sdf) = scene_sdf {
+                    self.stay_on_surface(scene_sdf);
+                }
+            }
+        }
+    }
+
+    fn fly(&mut self, ctx: &egui::Context, ts: f32) {
         if !ctx.wants_keyboard_input() {
             ctx.input(|i| {
                 if i.key_down(egui::Key::W) {
@@ -76,6 +195,91 @@ impl Camera {
         }
     }
 
+    /// Like flying but only along the surface, there is no moving along ana
+    fn walk(&mut self, ctx: &egui::Context, ts: f32) {
+        if !ctx.wants_keyboard_input() {
+            ctx.input(|i| {
+                if i.key_down(egui::Key::W) {
+                    self.position += self.walk_rotation.x() * self.move_speed * ts;
+                }
+                if i.key_down(egui::Key::S) {
+                    self.position -= self.walk_rotation.x() * self.move_speed * ts;
+                }
+                if i.key_down(egui::Key::A) {
+                    self.position -= self.walk_rotation.z() * self.move_speed * ts;
+                }
+                if i.key_down(egui::Key::D) {
+                    self.position += self.walk_rotation.z() * self.move_speed * ts;
+                }
+                if i.key_down(egui::Key::R) {
+                    self.position += self.walk_rotation.y() * self.move_speed * ts;
+                }
+                if i.key_down(egui::Key::F) {
+                    self.position -= self.walk_rotation.y() * self.move_speed * ts;
+                }
+
+                if i.key_down(egui::Key::ArrowLeft) {
+                    self.walk_rotation = self
+                        .walk_rotation
+                        .then(Rotor::rotate_xz(-self.rotate_speed * ts));
+                }
+                if i.key_down(egui::Key::ArrowRight) {
+                    self.walk_rotation = self
+                        .walk_rotation
+                        .then(Rotor::rotate_xz(self.rotate_speed * ts));
+                }
+                if i.key_down(egui::Key::ArrowUp) {
+                    self.xw_rotation += self.rotate_speed * ts;
+                }
+                if i.key_down(egui::Key::ArrowDown) {
+                    self.xw_rotation -= self.rotate_speed * ts;
+                }
+            });
+        }
+    }
+
+    /// Pushes the camera back out along the normal if it got closer to the surface than the minimum distance,
+    /// which keeps the part of the movement along the surface so that it slides along it
+    fn collide(
+        &mut self,
+        previous_position: Vector4<f32>,
+        scene_sdf: impl Fn(Vector4<f32>) -> f32,
+    ) {
+        let side = if scene_sdf(previous_position) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        // a few times for when pushing away from one part of the surface moves the camera into another
+        for _ in 0..4 {
+            let distance = scene_sdf(self.position) * side;
+            if distance >= self.min_distance {
+                break;
+            }
+            let normal = sdf::normal(&scene_sdf, self.position) * side;
+            self.position += normal * (self.min_distance - distance);
+        }
+    }
+
+    /// Moves the camera back to the eye height on whichever side of the surface it is on, and
+    /// parallel transports its orientation by turning ana to the new normal, which points away from the surface
+    fn stay_on_surface(&mut self, scene_sdf: impl Fn(Vector4<f32>) -> f32) {
+        let distance = self.eye_height.copysign(scene_sdf(self.position));
+        self.position = sdf::project(&scene_sdf, self.position, distance);
+
+        let normal = sdf::normal(&scene_sdf, self.position) * distance.signum();
+        if normal.square_magnitude() > 0.0 {
+            // turning by half a turn isn't well defined, so flip ana over first, which only happens
+            // when starting to walk on the other side of the surface to the fly rotation's ana
+            if self.walk_rotation.w().dot(normal) < 0.0 {
+                self.walk_rotation = self.walk_rotation.then(Rotor::rotate_yw(PI));
+            }
+            let correction_rotation = Rotor::from_to_vector(self.walk_rotation.w(), normal);
+            self.walk_rotation = correction_rotation.then(self.walk_rotation).normalised();
+        }
+    }
+
     pub fn ui(&mut self, ui: &mut egui::Ui) {
         egui::Grid::new("Camera").show(ui, |ui| {
             ui.label("Position:");
@@ -110,6 +314,62 @@ impl Camera {
             self.fov = self.fov.clamp(0.0, 179f32.to_radians());
             ui.end_row();
 
+            ui.label("View:");
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.view_mode, ViewMode::Embedded, "Embedded")
+                    .on_hover_text("Look at the surface from outside");
+                ui.selectable_value(&mut self.view_mode, ViewMode::Surface, "Surface")
+                    .on_hover_text("Look along the surface, as someone living in it would");
+            });
+            ui.end_row();
+
+            ui.label("Samples Per Pixel:").on_hover_text(
+                "More rays through each pixel smooth out the edges and patterns, but take longer",
+            );
+            ui.add(egui::DragValue::new(&mut self.samples_per_pixel).range(1..=64));
+            ui.end_row();
+
+            ui.label("Accumulate:").on_hover_text(
+                "Keep averaging the frames together while the camera and the scene are still",
+            );
+            ui.checkbox(&mut self.accumulate, "");
+            ui.end_row();
+
+            ui.label("Movement:");
+            ui.horizontal(|ui| {
+                let previous_movement = self.movement;
+                ui.selectable_value(&mut self.movement, Movement::Fly, "Fly")
+                    .on_hover_text("Fly freely through 4d space");
+                ui.selectable_value(&mut self.movement, Movement::Walk, "Walk")
+                    .on_hover_text(
+                        "Walk on the surface, through the wormholes from sheet to sheet",
+                    );
+                if self.movement == Movement::Walk && previous_movement != Movement::Walk {
+                    self.walk_rotation = Rotor::from_no_e4_rotor(self.base_rotation);
+                }
+            });
+            ui.end_row();
+
+            ui.label("Eye Height:");
+            ui.add(egui::DragValue::new(&mut self.eye_height).speed(0.05));
+            self.eye_height = self.eye_height.max(0.01);
+            ui.end_row();
+
+            ui.label("Collision:").on_hover_text(
+                "Stop at the minimum distance from the surface when flying, instead of passing through it",
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.collision, "");
+                ui.add_enabled(
+                    self.collision,
+                    egui::DragValue::new(&mut self.min_distance)
+                        .prefix("min distance:")
+                        .speed(0.01),
+                );
+                self.min_distance = self.min_distance.max(0.01);
+            });
+            ui.end_row();
+
             ui.label("Move Speed:");
             ui.add(egui::DragValue::new(&mut self.move_speed).speed(0.1));
             ui.end_row();
@@ -180,7 +440,11 @@ impl Camera {
     }
 
     pub fn rotation(&self) -> Rotor {
-        Rotor::from_no_e4_rotor(self.base_rotation).then(Rotor::rotate_xw(self.xw_rotation))
+        let base_rotation = match self.movement {
+            Movement::Fly => Rotor::from_no_e4_rotor(self.base_rotation),
+            Movement::Walk => self.walk_rotation,
+        };
+        base_rotation.then(Rotor::rotate_xw(self.xw_rotation))
     }
 
     pub fn transform(&self) -> Transform {
@@ -195,6 +459,42 @@ impl Camera {
             up: transform.w(),
             right: transform.z(),
             fov: self.fov,
+            samples_per_pixel: self.samples_per_pixel.max(1),
+            accumulate: self.accumulate as u32,
+        }
+    }
+
+    /// The camera moved onto the surface given by `scene_sdf`, with its axes projected onto the surface
+    /// so that rays from it start out along the surface
+    pub fn to_gpu_on_surface(&self, scene_sdf: impl Fn(Vector4<f32>) -> f32) -> GpuCamera {
+        let transform = self.transform();
+
+        let mut position = transform.position();
+        for _ in 0..8 {
+            position = sdf::project(&scene_sdf, position, 0.0);
+        }
+        let normal = sdf::normal(&scene_sdf, position);
+
+        // gram-schmidt starting from the normal so that the rest are tangent to the surface,
+        // ana is last so that it only replaces whichever axis is lost when looking along the normal
+        let mut axes = vec![normal];
+        for axis in [transform.x(), transform.z(), transform.w(), transform.y()] {
+            let tangent = axes.iter().fold(axis, |tangent, &previous| {
+                tangent - previous * tangent.dot(previous)
+            });
+            if tangent.square_magnitude() > 0.0001 {
+                axes.push(tangent.normalised());
+            }
+        }
+
+        GpuCamera {
+            position,
+            forward: axes[1],
+            up: axes[3],
+            right: axes[2],
+            fov: self.fov,
+            samples_per_pixel: self.samples_per_pixel.max(1),
+            accumulate: self.accumulate as u32,
         }
     }
 }
@@ -202,9 +502,11 @@ impl Camera {
 #[derive(Clone, Copy, NoUninit)]
 #[repr(C)]
 pub struct GpuCamera {
-    position: Vector4<f32>,
-    forward: Vector4<f32>,
-    up: Vector4<f32>,
-    right: Vector4<f32>,
-    fov: f32,
+    pub position: Vector4<f32>,
+    pub forward: Vector4<f32>,
+    pub up: Vector4<f32>,
+    pub right: Vector4<f32>,
+    pub fov: f32,
+    pub samples_per_pixel: u32,
+    pub accumulate: u32,
 }
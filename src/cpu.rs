@@ -0,0 +1,756 @@
+//! A CPU implementation of `trace_rays` and `path_trace_rays` in `ray_tracing.slang`, followed by
+//! `tonemap` in `tonemap.slang`, for rendering without a GPU.
+//!
+//! Everything in here must be kept in sync with the shader.
+
+use crate::{
+    camera::{GpuCamera, ViewMode},
+    lights::GpuLight,
+    objects::{GpuSphere, GpuWormhole, ObjectsInfo, wormhole_sdf},
+    path_tracing::GpuFrame,
+    scene::Scene,
+    sdf,
+    tonemapping::GpuTonemapping,
+};
+use math::{Vector3, Vector4};
+use std::{f32::consts::TAU, io, path::Path};
+
+/// An RGBA8 image, stored in the same row order as the output texture (row 0 is the bottom of the view)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    pub fn get(&self, x: u32, y: u32) -> [u8; 4] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Copies `image` over this one with its first pixel at `x`, `y`, leaving out anything that
+    /// doesn't fit
+    pub fn paste(&mut self, image: &Image, x: u32, y: u32) {
+        let width = image.width.min(self.width.saturating_sub(x)) as usize;
+        for row in 0..image.height.min(self.height.saturating_sub(y)) {
+            let from = (row * image.width) as usize;
+            let to = ((y + row) * self.width + x) as usize;
+            self.pixels[to..to + width].copy_from_slice(&image.pixels[from..from + width]);
+        }
+    }
+
+    /// Writes the image as an RGBA8 png, flipped so that it looks the same as it does in the viewport
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_png_with_text(path, &[])
+    }
+
+    /// [`Image::write_png`], with a UTF-8 text chunk for each keyword and text in `text`
+    pub fn write_png_with_text(
+        &self,
+        path: impl AsRef<Path>,
+        text: &[(&str, String)],
+    ) -> io::Result<()> {
+        let file = io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (keyword, text) in text {
+            encoder
+                .add_itxt_chunk(keyword.to_string(), text.clone())
+                .map_err(io::Error::other)?;
+        }
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+
+        let data = self
+            .pixels
+            .chunks(self.width as usize)
+            .rev()
+            .flatten()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        writer.write_image_data(&data).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    /// Reads an RGBA8 png written by [`Image::write_png`]
+    pub fn read_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = io::BufReader::new(std::fs::File::open(path)?);
+        let mut reader = png::Decoder::new(file)
+            .read_info()
+            .map_err(io::Error::other)?;
+        let mut data = vec![0; reader.output_buffer_size().unwrap_or(0)];
+        let info = reader.next_frame(&mut data).map_err(io::Error::other)?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "expected an 8 bit RGBA png, got {:?} {:?}",
+                    info.bit_depth, info.color_type
+                ),
+            ));
+        }
+
+        let pixels = data[..info.buffer_size()]
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect::<Vec<_>>();
+        let pixels = pixels
+            .chunks(info.width as usize)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Ray {
+    pub(crate) origin: Vector4<f32>,
+    pub(crate) direction: Vector4<f32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Hit {
+    position: Vector4<f32>,
+    /// Facing back along the ray
+    normal: Vector4<f32>,
+    /// Before lighting
+    color: Vector3<f32>,
+    pub(crate) distance: f32,
+}
+
+/// The same data that is bound to the shader
+pub(crate) struct Bindings<'a> {
+    pub(crate) camera: GpuCamera,
+    objects_info: ObjectsInfo,
+    sheets: &'a [f32],
+    wormholes: Vec<GpuWormhole>,
+    pub(crate) spheres: Vec<GpuSphere>,
+    lights: Vec<GpuLight>,
+}
+
+impl<'a> Bindings<'a> {
+    pub(crate) fn new(scene: &'a Scene) -> Self {
+        Self {
+            camera: scene.gpu_camera(),
+            objects_info: scene.objects_info(),
+            sheets: &scene.sheets,
+            wormholes: scene.gpu_wormholes(),
+            spheres: scene.gpu_spheres(),
+            lights: scene.gpu_lights(),
+        }
+    }
+
+    pub(crate) fn scene_sdf(&self, p: Vector4<f32>) -> f32 {
+        wormhole_sdf(self.sheets, &self.wormholes, p)
+    }
+}
+
+pub fn render(scene: &Scene, width: u32, height: u32) -> Image {
+    let bindings = Bindings::new(scene);
+    let trace = match scene.camera.view_mode {
+        ViewMode::Embedded => trace_ray,
+        ViewMode::Surface => trace_surface_ray,
+    };
+    // a single frame, so accumulating changes nothing
+    let frame = GpuFrame {
+        index: 0,
+        max_bounces: 0,
+        offset: [0, 0],
+        size: [width, height],
+    };
+    render_pixels(scene, width, height, |x, y| trace(&bindings, &frame, x, y))
+}
+
+/// Renders `samples` frames of progressive path tracing and returns the last, which is the average
+/// of all of them
+///
+/// The surface view isn't path traced, so it is rendered the same as by [`render`]
+pub fn render_path_traced(
+    scene: &Scene,
+    width: u32,
+    height: u32,
+    samples: u32,
+    max_bounces: u32,
+) -> Image {
+    if scene.camera.view_mode == ViewMode::Surface {
+        return render(scene, width, height);
+    }
+
+    let bindings = Bindings::new(scene);
+    render_pixels(scene, width, height, |x, y| {
+        let mut accumulated = Vector3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        for index in 0..samples {
+            let frame = GpuFrame {
+                index,
+                max_bounces,
+                offset: [0, 0],
+                size: [width, height],
+            };
+            accumulated += path_trace_ray(&bindings, &frame, x, y);
+        }
+        accumulated / samples.max(1) as f32
+    })
+}
+
+/// Tonemaps the HDR color that `pixel` gives for each pixel
+fn render_pixels(
+    scene: &Scene,
+    width: u32,
+    height: u32,
+    pixel: impl Fn(u32, u32) -> Vector3<f32> + Sync,
+) -> Image {
+    let tonemapping = scene.tonemapping.to_gpu();
+    let pixel = |x, y| to_unorm(tonemap(&tonemapping, pixel(x, y)));
+
+    let mut pixels = vec![[0; 4]; width as usize * height as usize];
+    if !pixels.is_empty() {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let rows_per_chunk = (height as usize).div_ceil(threads);
+        std::thread::scope(|s| {
+            for (chunk_index, chunk) in pixels
+                .chunks_mut(rows_per_chunk * width as usize)
+                .enumerate()
+            {
+                let pixel = &pixel;
+                s.spawn(move || {
+                    for (i, color) in chunk.iter_mut().enumerate() {
+                        let x = (i % width as usize) as u32;
+                        let y = (chunk_index * rows_per_chunk + i / width as usize) as u32;
+                        *color = pixel(x, y);
+                    }
+                });
+            }
+        });
+    }
+
+    Image {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// `x` and `y` are the point in the view that the ray goes through, in pixels
+pub(crate) fn camera_ray(camera: &GpuCamera, x: f32, y: f32, width: u32, height: u32) -> Ray {
+    let aspect = width as f32 / height as f32;
+    let u = (x / width as f32) * 2.0 - 1.0;
+    let v = (y / height as f32) * 2.0 - 1.0;
+    let fov_scale = f32::tan(camera.fov * 0.5);
+
+    Ray {
+        origin: camera.position,
+        direction: (camera.forward
+            + camera.up * v * fov_scale
+            + camera.right * u * fov_scale * aspect)
+            .normalised(),
+    }
+}
+
+/// Same rounding as a store to a unorm texture
+fn to_unorm(color: Vector3<f32>) -> [u8; 4] {
+    let to_unorm = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [to_unorm(color.x), to_unorm(color.y), to_unorm(color.z), 255]
+}
+
+fn trace_ray(bindings: &Bindings<'_>, frame: &GpuFrame, x: u32, y: u32) -> Vector3<f32> {
+    let accumulate = bindings.camera.accumulate != 0;
+    sample_pixel(
+        bindings,
+        frame,
+        x,
+        y,
+        accumulate,
+        |ray, _| match hit_nearest(bindings, ray) {
+            Some(hit) => light_surface(bindings, hit.color, hit.position, hit.normal),
+            None => Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        },
+    )
+}
+
+/// One frame's sample of a pixel, before it is added to the others
+fn path_trace_ray(bindings: &Bindings<'_>, frame: &GpuFrame, x: u32, y: u32) -> Vector3<f32> {
+    sample_pixel(bindings, frame, x, y, true, |ray, seed| {
+        path_trace(bindings, frame, ray, seed)
+    })
+}
+
+fn trace_surface_ray(bindings: &Bindings<'_>, frame: &GpuFrame, x: u32, y: u32) -> Vector3<f32> {
+    let accumulate = bindings.camera.accumulate != 0;
+    sample_pixel(bindings, frame, x, y, accumulate, |ray, _| {
+        follow_surface(bindings, ray)
+    })
+}
+
+/// The average of the camera's samples per pixel of the pixel at `x`, `y` this frame, with
+/// `accumulate` when the frames are averaged together, which gives each frame different points in
+/// the pixel so that the edges are smoothed as well
+fn sample_pixel(
+    bindings: &Bindings<'_>,
+    frame: &GpuFrame,
+    x: u32,
+    y: u32,
+    accumulate: bool,
+    mut trace: impl FnMut(Ray, &mut u32) -> Vector3<f32>,
+) -> Vector3<f32> {
+    let camera = &bindings.camera;
+    // without more than one sample there is nothing to spread out, so the ray goes through the
+    // centre of the pixel to keep the image still
+    let jitter = camera.samples_per_pixel > 1 || accumulate;
+    let mut seed = pixel_seed(x, y, if accumulate { frame.index } else { 0 });
+
+    let mut color = Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    for i in 0..camera.samples_per_pixel {
+        let (offset_x, offset_y) = if jitter {
+            sample_offset(camera, i, &mut seed)
+        } else {
+            (0.5, 0.5)
+        };
+        let ray = camera_ray(
+            camera,
+            x as f32 + offset_x,
+            y as f32 + offset_y,
+            frame.size[0],
+            frame.size[1],
+        );
+        color += trace(ray, &mut seed);
+    }
+
+    color / camera.samples_per_pixel as f32
+}
+
+fn pixel_seed(x: u32, y: u32, frame_index: u32) -> u32 {
+    random::hash(x.wrapping_add(random::hash(y.wrapping_add(random::hash(frame_index)))))
+}
+
+/// A random point in the `i`th cell of a grid over the pixel, with a cell for each sample, so that
+/// the samples of a frame cover the whole pixel instead of clumping together
+fn sample_offset(camera: &GpuCamera, i: u32, seed: &mut u32) -> (f32, f32) {
+    let columns = f32::sqrt(camera.samples_per_pixel as f32).ceil() as u32;
+    let rows = camera.samples_per_pixel.div_ceil(columns);
+    let jitter_x = random::next(seed);
+    let jitter_y = random::next(seed);
+    (
+        ((i % columns) as f32 + jitter_x) / columns as f32,
+        ((i / columns) as f32 + jitter_y) / rows as f32,
+    )
+}
+
+/// `tonemap.slang`, apart from clamping, which is left to [`to_unorm`]
+fn tonemap(tonemapping: &GpuTonemapping, color: Vector3<f32>) -> Vector3<f32> {
+    let color = apply_curve(tonemapping, color * tonemapping.exposure);
+    if tonemapping.srgb != 0 {
+        color.map(srgb_from_linear)
+    } else {
+        color
+    }
+}
+
+fn apply_curve(tonemapping: &GpuTonemapping, color: Vector3<f32>) -> Vector3<f32> {
+    match tonemapping.curve {
+        1 => color.map(|c| c / (1.0 + c)),
+        // with the exposure bias and white point from the talk
+        2 => color.map(|c| hable(c * 2.0) / hable(11.2)),
+        3 => color.map(|c| (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)),
+        _ => color,
+    }
+}
+
+/// John Hable's filmic curve, from "Uncharted 2: HDR Lighting"
+fn hable(x: f32) -> f32 {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+fn srgb_from_linear(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.max(0.0).powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn xyz(v: Vector4<f32>) -> Vector3<f32> {
+    Vector3 {
+        x: v.x,
+        y: v.y,
+        z: v.z,
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn checker(p: Vector4<f32>, scale: f32) -> f32 {
+    let kind = f32::sin(p.x * scale)
+        + f32::sin(p.y * scale)
+        + f32::sin(p.z * scale)
+        + f32::sin(p.w * scale);
+    if kind > 0.0 { 1.0 } else { 0.5 }
+}
+
+pub(crate) fn hit_sphere(sphere: &GpuSphere, ray: Ray) -> Option<Hit> {
+    let oc = sphere.position - ray.origin;
+    let a = ray.direction.dot(ray.direction);
+    let h = ray.direction.dot(oc);
+    let c = oc.dot(oc) - sphere.radius * sphere.radius;
+    let discriminant = h * h - a * c;
+
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let d1 = (h - discriminant.sqrt()) / a;
+
+    if d1 < 0.0 {
+        return None;
+    }
+
+    let distance = d1;
+    let position = ray.origin + ray.direction * distance;
+
+    Some(Hit {
+        position,
+        normal: (position - sphere.position) / sphere.radius,
+        color: sphere_albedo(sphere, sphere.position, position),
+        distance,
+    })
+}
+
+/// The color of the sphere's pattern at `position`, where `centre` is where the sphere is drawn
+fn sphere_albedo(sphere: &GpuSphere, centre: Vector4<f32>, position: Vector4<f32>) -> Vector3<f32> {
+    let relative_hit_pos = position - centre;
+    let projected_hit_pos = Vector4 {
+        x: relative_hit_pos.dot(sphere.forward),
+        y: relative_hit_pos.dot(sphere.up),
+        z: relative_hit_pos.dot(sphere.right),
+        w: relative_hit_pos.dot(sphere.ana),
+    };
+
+    sphere.color * checker(projected_hit_pos, sphere.pattern_scale)
+}
+
+/// `centre` is where the sphere is drawn, which isn't its position when it is seen from on the surface
+fn shade_sphere(
+    sphere: &GpuSphere,
+    centre: Vector4<f32>,
+    position: Vector4<f32>,
+    direction: Vector4<f32>,
+) -> Vector3<f32> {
+    let normal = (position - centre) / sphere.radius;
+    let facing = f32::max(-normal.dot(direction), 0.0);
+
+    sphere_albedo(sphere, centre, position) * lerp(0.3, 1.0, facing)
+}
+
+/// Ambient light plus the light that reaches `position` from each light, with Lambertian shading
+fn light_surface(
+    bindings: &Bindings<'_>,
+    albedo: Vector3<f32>,
+    position: Vector4<f32>,
+    normal: Vector4<f32>,
+) -> Vector3<f32> {
+    let ambient = bindings.objects_info.ambient;
+    let ambient = Vector3 {
+        x: ambient,
+        y: ambient,
+        z: ambient,
+    };
+    albedo * (ambient + direct_light(bindings, position, normal))
+}
+
+/// The light that reaches `position` straight from each light, with Lambertian shading
+fn direct_light(
+    bindings: &Bindings<'_>,
+    position: Vector4<f32>,
+    normal: Vector4<f32>,
+) -> Vector3<f32> {
+    let mut light = Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    // start the shadow rays off the surface, so that they don't stop where they start
+    let origin = position + normal * 0.01;
+
+    for gpu_light in &bindings.lights {
+        let mut color = gpu_light.color;
+        let (direction, distance) = if gpu_light.kind == 0 {
+            (-gpu_light.vector, 1000.0)
+        } else {
+            let offset = gpu_light.vector - position;
+            let distance = offset.magnitude();
+            color /= distance * distance;
+            (offset / distance, distance)
+        };
+
+        let lambert = normal.dot(direction);
+        if lambert <= 0.0 {
+            continue;
+        }
+
+        let shadow_ray = Ray { origin, direction };
+        light += color
+            * lambert
+            * soft_shadow(bindings, shadow_ray, distance, gpu_light.shadow_hardness);
+    }
+
+    light
+}
+
+/// The light that comes back along `ray` after bouncing around the scene up to
+/// `frame.max_bounces` times, where everything is diffuse with a thin glossy coat, and the ambient
+/// light comes from everywhere that the rays escape to
+fn path_trace(
+    bindings: &Bindings<'_>,
+    frame: &GpuFrame,
+    mut ray: Ray,
+    seed: &mut u32,
+) -> Vector3<f32> {
+    let mut color = Vector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    let mut throughput = Vector3 {
+        x: 1.0,
+        y: 1.0,
+        z: 1.0,
+    };
+    for _ in 0..=frame.max_bounces {
+        let Some(hit) = hit_nearest(bindings, ray) else {
+            color += throughput * bindings.objects_info.ambient;
+            break;
+        };
+
+        ray.origin = hit.position + hit.normal * 0.01;
+
+        // Schlick's approximation of how much the coat reflects, for a coat like varnish
+        let facing = f32::max(-ray.direction.dot(hit.normal), 0.0);
+        let reflectance = 0.04 + 0.96 * (1.0 - facing).powi(5);
+        if random::next(seed) < reflectance {
+            ray.direction = ray.direction - hit.normal * 2.0 * ray.direction.dot(hit.normal);
+        } else {
+            throughput *= hit.color;
+            color += throughput * direct_light(bindings, hit.position, hit.normal);
+            ray.direction = random::cosine_hemisphere(hit.normal, seed);
+        }
+    }
+    color
+}
+
+/// How much of the light along `ray` gets through, with a penumbra estimated from how closely the
+/// ray passes the surface and the spheres
+fn soft_shadow(bindings: &Bindings<'_>, mut ray: Ray, max_distance: f32, hardness: f32) -> f32 {
+    let mut shadow = 1.0f32;
+    let mut total_distance = 0.0;
+    for _ in 0..256 {
+        let mut distance = f32::abs(bindings.scene_sdf(ray.origin));
+        for sphere in &bindings.spheres {
+            distance = distance.min((ray.origin - sphere.position).magnitude() - sphere.radius);
+        }
+
+        if distance < 0.001 {
+            return 0.0;
+        }
+        if total_distance > 0.0 {
+            shadow = shadow.min(hardness * distance / total_distance);
+        }
+
+        ray.origin += ray.direction * distance;
+        total_distance += distance;
+
+        if total_distance >= max_distance {
+            break;
+        }
+    }
+    shadow
+}
+
+/// Whichever of the surface and the spheres `ray` hits first
+fn hit_nearest(bindings: &Bindings<'_>, ray: Ray) -> Option<Hit> {
+    let mut hit = hit_scene(bindings, ray);
+
+    for sphere in &bindings.spheres {
+        let new_hit = hit_sphere(sphere, ray);
+        if let Some(new_hit) = new_hit
+            && hit.is_none_or(|hit| new_hit.distance < hit.distance)
+        {
+            hit = Some(new_hit);
+        }
+    }
+
+    hit
+}
+
+pub(crate) fn hit_scene(bindings: &Bindings<'_>, mut ray: Ray) -> Option<Hit> {
+    let scene_sdf = |p| bindings.scene_sdf(p);
+
+    let mut total_distance = 0.0;
+    for _ in 0..1000 {
+        let distance = f32::abs(scene_sdf(ray.origin));
+
+        if distance < 0.001 {
+            let scale = 50.0;
+            let normal = sdf::normal(scene_sdf, ray.origin);
+            let color = xyz(normal) * 0.5 + 0.5;
+
+            return Some(Hit {
+                position: ray.origin,
+                normal: if normal.dot(ray.direction) > 0.0 {
+                    -normal
+                } else {
+                    normal
+                },
+                color: color * checker(ray.origin, scale),
+                distance: total_distance,
+            });
+        }
+
+        ray.origin += ray.direction * distance;
+        total_distance += distance;
+
+        if total_distance >= 1000.0 {
+            break;
+        }
+    }
+    None
+}
+
+/// What a ray that starts on the surface sees as it follows the surface, which is a geodesic
+/// that can go through the wormholes into other sheets
+fn follow_surface(bindings: &Bindings<'_>, mut ray: Ray) -> Vector3<f32> {
+    let scene_sdf = |p| bindings.scene_sdf(p);
+
+    let mut total_distance = 0.0;
+    for _ in 0..1000 {
+        // spheres are seen where they touch the surface, as balls of the same radius
+        let mut nearest_distance = f32::MAX;
+        let mut nearest_sphere = None;
+        for sphere in &bindings.spheres {
+            let distance = (ray.origin - sphere.surface_position).magnitude() - sphere.radius;
+            if distance < nearest_distance {
+                nearest_distance = distance;
+                nearest_sphere = Some(sphere);
+            }
+        }
+
+        if let Some(sphere) = nearest_sphere
+            && nearest_distance < 0.001
+        {
+            return shade_sphere(sphere, sphere.surface_position, ray.origin, ray.direction);
+        }
+
+        // short steps so that the ray doesn't cut across the curves of the throats
+        let step = f32::min(nearest_distance, 0.25);
+        ray.origin += ray.direction * step;
+        total_distance += step;
+
+        if total_distance >= 100.0 {
+            break;
+        }
+
+        // back onto the surface, and turned to follow it
+        let normal = sdf::normal(scene_sdf, ray.origin);
+        ray.origin -= normal * scene_sdf(ray.origin);
+        ray.direction = (ray.direction - normal * ray.direction.dot(normal)).normalised();
+    }
+
+    sheet_color(bindings, ray)
+}
+
+/// A pattern in the direction of the ray, tinted by the sheet it ended up on
+fn sheet_color(bindings: &Bindings<'_>, ray: Ray) -> Vector3<f32> {
+    let nearest = bindings
+        .sheets
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            f32::abs(*a - ray.origin.w).total_cmp(&f32::abs(*b - ray.origin.w))
+        })
+        .map_or(0, |(i, _)| i);
+
+    let hue = nearest as f32 * 0.3;
+    let tint = Vector3 {
+        x: hue,
+        y: hue + 1.0 / 3.0,
+        z: hue + 2.0 / 3.0,
+    }
+    .map(|c| 0.5 + 0.5 * f32::cos(TAU * c));
+    tint * lerp(0.4, 1.0, ray.direction.y * 0.5 + 0.5) * checker(ray.direction, 10.0)
+}
+
+/// `random.slang`
+mod random {
+    use math::Vector4;
+    use std::f32::consts::TAU;
+
+    /// PCG hash, from "Hash Functions for GPU Rendering" by Jarzynski and Olano
+    pub(super) fn hash(x: u32) -> u32 {
+        let state = x.wrapping_mul(747796405).wrapping_add(2891336453);
+        let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+        (word >> 22) ^ word
+    }
+
+    /// A number in [0, 1), moving `seed` on so that the next call gives a different one
+    pub(super) fn next(seed: &mut u32) -> f32 {
+        *seed = hash(*seed);
+        (*seed >> 8) as f32 / 16777216.0
+    }
+
+    /// A direction in the hemisphere around `normal`, with a density proportional to the cosine
+    /// of its angle from `normal`
+    ///
+    /// Points spread evenly through the ball of directions along the surface are lifted straight
+    /// up onto the hemisphere, which is Malley's method with one more dimension
+    pub(super) fn cosine_hemisphere(normal: Vector4<f32>, seed: &mut u32) -> Vector4<f32> {
+        // a gaussian is the same in every direction, so with the part along the normal removed it
+        // points evenly in every direction along the surface
+        let radius1 = f32::sqrt(-2.0 * f32::ln(next(seed).max(1e-7)));
+        let angle1 = next(seed) * TAU;
+        let radius2 = f32::sqrt(-2.0 * f32::ln(next(seed).max(1e-7)));
+        let angle2 = next(seed) * TAU;
+        let gaussian = Vector4 {
+            x: radius1 * angle1.cos(),
+            y: radius1 * angle1.sin(),
+            z: radius2 * angle2.cos(),
+            w: radius2 * angle2.sin(),
+        };
+
+        let tangent = gaussian - normal * gaussian.dot(normal);
+        let tangent_length = tangent.magnitude();
+        if tangent_length < 1e-6 {
+            return normal;
+        }
+
+        // the cube root spreads the points evenly through the volume of the ball
+        let radius = next(seed).powf(1.0 / 3.0);
+        tangent / tangent_length * radius + normal * f32::sqrt(f32::max(1.0 - radius * radius, 0.0))
+    }
+}
@@ -0,0 +1,251 @@
+//! Handles drawn over the viewport for moving and rotating the selected object with the mouse.
+
+use crate::{
+    camera::GpuCamera,
+    objects::{Sphere, Wormhole},
+    picking,
+};
+use eframe::egui;
+use math::{Rotor, Vector4};
+use std::f32::consts::TAU;
+
+/// How long each axis appears on screen, in points
+const AXIS_LENGTH: f32 = 80.0;
+/// Radius of the rotation arcs, relative to the axes
+const ARC_RADIUS: f32 = 0.6;
+/// Size of the knob at the end of each axis and on each arc
+const HANDLE_SIZE: f32 = 12.0;
+/// Axes shorter than this on screen point too far out of the view to be dragged along
+const MIN_AXIS_LENGTH: f32 = 6.0;
+
+const AXIS_NAMES: [&str; 4] = ["x", "y", "z", "w"];
+const AXIS_COLORS: [egui::Color32; 4] = [
+    egui::Color32::from_rgb(230, 60, 60),
+    egui::Color32::from_rgb(60, 200, 60),
+    egui::Color32::from_rgb(70, 110, 240),
+    egui::Color32::from_rgb(230, 190, 40),
+];
+/// A rotation by an angle in one plane
+type PlaneRotation = fn(f32) -> Rotor;
+/// The two axes of each plane, and the rotation in it that turns the first towards the second
+const PLANES: [(usize, usize, PlaneRotation); 6] = [
+    (0, 1, Rotor::rotate_xy),
+    (0, 2, Rotor::rotate_xz),
+    (0, 3, Rotor::rotate_xw),
+    (1, 2, Rotor::rotate_yz),
+    (1, 3, Rotor::rotate_yw),
+    (2, 3, Rotor::rotate_zw),
+];
+
+/// The viewport in `rect` seen through `camera`
+struct View<'a> {
+    camera: &'a GpuCamera,
+    rect: egui::Rect,
+}
+
+impl View<'_> {
+    fn to_screen(&self, p: Vector4<f32>) -> Option<egui::Pos2> {
+        picking::screen_position(self.camera, p, self.rect.width(), self.rect.height())
+            .map(|[x, y]| egui::pos2(self.rect.left() + x, self.rect.bottom() - y))
+    }
+
+    /// The length at `p` that appears [`AXIS_LENGTH`] long on screen
+    fn axis_length(&self, p: Vector4<f32>) -> f32 {
+        let depth = (p - self.camera.position).dot(self.camera.forward);
+        let fov_scale = f32::tan(self.camera.fov * 0.5);
+        depth * fov_scale * AXIS_LENGTH / (self.rect.height() * 0.5)
+    }
+}
+
+fn handle(ui: &egui::Ui, id: egui::Id, position: egui::Pos2) -> egui::Response {
+    // clicks are sensed too, so that they don't go through to the viewport and change the selection
+    ui.interact(
+        egui::Rect::from_center_size(position, egui::Vec2::splat(HANDLE_SIZE)),
+        id,
+        egui::Sense::click_and_drag(),
+    )
+}
+
+fn stroke(response: &egui::Response, color: egui::Color32) -> egui::Stroke {
+    let width = if response.hovered() || response.dragged() {
+        3.5
+    } else {
+        2.0
+    };
+    egui::Stroke::new(width, color)
+}
+
+/// Draws an arrow along each of `axes` from `position`, returning how far `position` was dragged
+fn translation_handles(
+    ui: &egui::Ui,
+    view: &View<'_>,
+    id: egui::Id,
+    position: Vector4<f32>,
+    axes: &[Vector4<f32>],
+) -> Option<Vector4<f32>> {
+    let centre = view.to_screen(position)?;
+    let length = view.axis_length(position);
+
+    let mut offset = None;
+    for (i, &axis) in axes.iter().enumerate() {
+        let Some(end) = view.to_screen(position + axis * length) else {
+            continue;
+        };
+        let screen_axis = end - centre;
+        if screen_axis.length() < MIN_AXIS_LENGTH {
+            continue;
+        }
+
+        let response = handle(ui, id.with(("translate", i)), end);
+        let drag = response.drag_delta();
+        if drag != egui::Vec2::ZERO {
+            let amount = drag.dot(screen_axis) / screen_axis.length_sq() * length;
+            *offset.get_or_insert(Vector4 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 0.0,
+            }) += axis * amount;
+        }
+
+        let painter = ui.painter();
+        let stroke = stroke(&response, AXIS_COLORS[i]);
+        painter.line_segment([centre, end], stroke);
+        painter.circle_filled(end, HANDLE_SIZE * 0.5, stroke.color);
+        painter.text(
+            end + screen_axis.normalized() * HANDLE_SIZE,
+            egui::Align2::CENTER_CENTER,
+            AXIS_NAMES[i],
+            egui::FontId::proportional(14.0),
+            stroke.color,
+        );
+    }
+    offset
+}
+
+/// Draws an arc between each pair of `axes` around `position`, returning the rotation in `axes`
+/// that they were dragged by
+fn rotation_handles(
+    ui: &egui::Ui,
+    view: &View<'_>,
+    id: egui::Id,
+    position: Vector4<f32>,
+    axes: [Vector4<f32>; 4],
+) -> Option<Rotor> {
+    const SEGMENTS: usize = 16;
+    let radius = view.axis_length(position) * ARC_RADIUS;
+
+    let mut rotation = None;
+    for (i, &(a, b, rotate)) in PLANES.iter().enumerate() {
+        let Some(arc) = (0..=SEGMENTS)
+            .map(|k| {
+                let angle = k as f32 / SEGMENTS as f32 * TAU * 0.25;
+                view.to_screen(position + (axes[a] * angle.cos() + axes[b] * angle.sin()) * radius)
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+
+        // the knob is halfway along the arc, and moves along it as the plane is rotated
+        let knob = arc[SEGMENTS / 2];
+        let tangent =
+            (arc[SEGMENTS / 2 + 1] - arc[SEGMENTS / 2 - 1]) / (2.0 * TAU * 0.25 / SEGMENTS as f32);
+        if tangent.length() < MIN_AXIS_LENGTH {
+            continue;
+        }
+
+        let response = handle(ui, id.with(("rotate", i)), knob);
+        let drag = response.drag_delta();
+        if drag != egui::Vec2::ZERO {
+            let angle = drag.dot(tangent) / tangent.length_sq();
+            let drag_rotation = rotate(angle);
+            rotation = Some(rotation.map_or(drag_rotation, |rotation: Rotor| {
+                rotation.then(drag_rotation)
+            }));
+        }
+
+        let [color_a, color_b] = [AXIS_COLORS[a], AXIS_COLORS[b]];
+        let color = egui::Color32::from_rgb(
+            ((color_a.r() as u16 + color_b.r() as u16) / 2) as u8,
+            ((color_a.g() as u16 + color_b.g() as u16) / 2) as u8,
+            ((color_a.b() as u16 + color_b.b() as u16) / 2) as u8,
+        );
+        let painter = ui.painter();
+        let stroke = stroke(&response, color);
+        painter.add(egui::Shape::line(arc, stroke));
+        painter.circle_stroke(knob, HANDLE_SIZE * 0.4, stroke);
+    }
+    rotation
+}
+
+/// Moves `sphere` along its own axes and rotates it in the planes between them, returning whether it
+/// was changed
+pub fn sphere(ui: &egui::Ui, rect: egui::Rect, camera: &GpuCamera, sphere: &mut Sphere) -> bool {
+    let view = View { camera, rect };
+    let id = ui.id().with("Sphere Gizmo");
+    let axes = [
+        sphere.rotation.x(),
+        sphere.rotation.y(),
+        sphere.rotation.z(),
+        sphere.rotation.w(),
+    ];
+
+    let rotation = rotation_handles(ui, &view, id, sphere.position, axes);
+    let offset = translation_handles(ui, &view, id, sphere.position, &axes);
+
+    if let Some(rotation) = rotation {
+        sphere.rotation = sphere.rotation.then(rotation).normalised();
+    }
+    if let Some(offset) = offset {
+        sphere.position += offset;
+    }
+    rotation.is_some() || offset.is_some()
+}
+
+/// Moves the mouth of `wormhole` on its lower sheet, at `w`, along that sheet, returning whether it
+/// was changed
+pub fn wormhole(
+    ui: &egui::Ui,
+    rect: egui::Rect,
+    camera: &GpuCamera,
+    wormhole: &mut Wormhole,
+    w: f32,
+) -> bool {
+    let view = View { camera, rect };
+    let id = ui.id().with("Wormhole Gizmo");
+    let position = Vector4 {
+        x: wormhole.position.x,
+        y: wormhole.position.y,
+        z: wormhole.position.z,
+        w,
+    };
+    let axes = [
+        Vector4 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+            w: 0.0,
+        },
+        Vector4 {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+            w: 0.0,
+        },
+        Vector4 {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+            w: 0.0,
+        },
+    ];
+
+    let Some(offset) = translation_handles(ui, &view, id, position, &axes) else {
+        return false;
+    };
+    wormhole.position.x += offset.x;
+    wormhole.position.y += offset.y;
+    wormhole.position.z += offset.z;
+    true
+}
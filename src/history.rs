@@ -0,0 +1,206 @@
+//! Undo and redo for edits to the scene, kept as the edits themselves rather than as copies.
+
+use crate::{
+    animation::Keyframe,
+    camera::Bookmark,
+    lights::Light,
+    objects::{Sphere, Wormhole},
+    scene::Scene,
+};
+use eframe::egui;
+
+/// A change to one item in a list
+#[derive(Debug, Clone)]
+pub enum ListEdit<T> {
+    Add { index: usize, item: T },
+    Remove { index: usize, item: T },
+    Change { index: usize, before: T, after: T },
+}
+
+impl<T: Clone> ListEdit<T> {
+    /// The edit that undoes this one
+    fn inverse(self) -> Self {
+        match self {
+            ListEdit::Add { index, item } => ListEdit::Remove { index, item },
+            ListEdit::Remove { index, item } => ListEdit::Add { index, item },
+            ListEdit::Change {
+                index,
+                before,
+                after,
+            } => ListEdit::Change {
+                index,
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    /// Does nothing if `list` was changed without being recorded, leaving the index out of range
+    fn apply(&self, list: &mut Vec<T>) {
+        match self {
+            ListEdit::Add { index, item } => list.insert((*index).min(list.len()), item.clone()),
+            ListEdit::Remove { index, .. } => {
+                if *index < list.len() {
+                    list.remove(*index);
+                }
+            }
+            ListEdit::Change { index, after, .. } => {
+                if let Some(item) = list.get_mut(*index) {
+                    *item = after.clone();
+                }
+            }
+        }
+    }
+
+    /// Folds `next` into this edit if they both change the same item
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (
+                ListEdit::Change { index, after, .. },
+                ListEdit::Change {
+                    index: next_index,
+                    after: next_after,
+                    ..
+                },
+            ) if index == next_index => {
+                *after = next_after.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Edit {
+    Wormhole(ListEdit<Wormhole>),
+    Sphere(ListEdit<Sphere>),
+    Bookmark(ListEdit<Bookmark>),
+    Keyframe(ListEdit<Keyframe>),
+    Light(ListEdit<Light>),
+}
+
+impl Edit {
+    fn inverse(self) -> Self {
+        match self {
+            Edit::Wormhole(edit) => Edit::Wormhole(edit.inverse()),
+            Edit::Sphere(edit) => Edit::Sphere(edit.inverse()),
+            Edit::Bookmark(edit) => Edit::Bookmark(edit.inverse()),
+            Edit::Keyframe(edit) => Edit::Keyframe(edit.inverse()),
+            Edit::Light(edit) => Edit::Light(edit.inverse()),
+        }
+    }
+
+    fn apply(&self, scene: &mut Scene) {
+        match self {
+            Edit::Wormhole(edit) => edit.apply(&mut scene.wormholes),
+            Edit::Sphere(edit) => edit.apply(&mut scene.spheres),
+            Edit::Bookmark(edit) => edit.apply(&mut scene.bookmarks),
+            Edit::Keyframe(edit) => edit.apply(&mut scene.keyframes),
+            Edit::Light(edit) => edit.apply(&mut scene.lights),
+        }
+    }
+
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (Edit::Wormhole(edit), Edit::Wormhole(next)) => edit.merge(next),
+            (Edit::Sphere(edit), Edit::Sphere(next)) => edit.merge(next),
+            (Edit::Bookmark(edit), Edit::Bookmark(next)) => edit.merge(next),
+            (Edit::Keyframe(edit), Edit::Keyframe(next)) => edit.merge(next),
+            (Edit::Light(edit), Edit::Light(next)) => edit.merge(next),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// The widget being dragged or typed into when the last edit was pushed, further edits from it
+    /// are merged into that one until it is let go of
+    interacting: Option<egui::Id>,
+}
+
+/// The widget that is being dragged or typed into, if any
+fn interacting(ctx: &egui::Context) -> Option<egui::Id> {
+    ctx.dragged_id().or_else(|| {
+        ctx.wants_keyboard_input()
+            .then(|| ctx.memory(|memory| memory.focused()))
+            .flatten()
+    })
+}
+
+impl History {
+    /// Records an edit that has already been made to the scene
+    pub fn push(&mut self, ctx: &egui::Context, edit: Edit) {
+        self.redo.clear();
+
+        let interacting = interacting(ctx);
+        if interacting.is_some()
+            && interacting == self.interacting
+            && let Some(last) = self.undo.last_mut()
+            && last.merge(&edit)
+        {
+            return;
+        }
+        self.interacting = interacting;
+        self.undo.push(edit);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self, scene: &mut Scene) {
+        if let Some(edit) = self.undo.pop() {
+            edit.clone().inverse().apply(scene);
+            self.redo.push(edit);
+        }
+        self.interacting = None;
+    }
+
+    pub fn redo(&mut self, scene: &mut Scene) {
+        if let Some(edit) = self.redo.pop() {
+            edit.apply(scene);
+            self.undo.push(edit);
+        }
+        self.interacting = None;
+    }
+
+    /// Forgets everything, for when the scene is replaced
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Handles the undo and redo shortcuts, and stops merging edits once their widget is let go of.
+    /// Returns whether the scene was changed
+    pub fn update(&mut self, ctx: &egui::Context, scene: &mut Scene) -> bool {
+        if interacting(ctx).is_none() {
+            self.interacting = None;
+        }
+        // text fields have their own undo
+        if ctx.wants_keyboard_input() {
+            return false;
+        }
+
+        let redo = egui::KeyboardShortcut::new(
+            egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+            egui::Key::Z,
+        );
+        let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+        if ctx.input_mut(|input| input.consume_shortcut(&redo)) && self.can_redo() {
+            self.redo(scene);
+            true
+        } else if ctx.input_mut(|input| input.consume_shortcut(&undo)) && self.can_undo() {
+            self.undo(scene);
+            true
+        } else {
+            false
+        }
+    }
+}
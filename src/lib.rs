@@ -0,0 +1,15 @@
+pub mod animation;
+pub mod camera;
+pub mod cpu;
+pub mod gizmo;
+pub mod history;
+pub mod lights;
+pub mod objects;
+pub mod path_tracing;
+pub mod physics;
+pub mod picking;
+pub mod render_scale;
+pub mod scene;
+pub mod screenshot;
+pub mod sdf;
+pub mod tonemapping;
@@ -0,0 +1,75 @@
+use bytemuck::NoUninit;
+use math::{Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LightKind {
+    /// Parallel light from infinitely far away
+    Directional,
+    /// Light spreading out from a point, which falls off with the square of the distance like it
+    /// does in 3d rather than the cube it would in 4d, so that it reaches across the scene
+    #[default]
+    Point,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Light {
+    pub kind: LightKind,
+    /// The direction that a directional light shines in, or the position of a point light
+    pub vector: Vector4<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// How sharp the edges of shadows are, lower is softer
+    pub shadow_hardness: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            kind: LightKind::Point,
+            vector: Vector4 {
+                x: 3.0,
+                y: 2.0,
+                z: 1.0,
+                w: 5.0,
+            },
+            color: Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            intensity: 30.0,
+            shadow_hardness: 8.0,
+        }
+    }
+}
+
+impl Light {
+    pub fn to_gpu(&self) -> GpuLight {
+        GpuLight {
+            vector: match self.kind {
+                LightKind::Directional => self.vector.normalised(),
+                LightKind::Point => self.vector,
+            },
+            color: self.color * self.intensity,
+            kind: match self.kind {
+                LightKind::Directional => 0,
+                LightKind::Point => 1,
+            },
+            shadow_hardness: self.shadow_hardness,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, NoUninit)]
+#[repr(C)]
+pub struct GpuLight {
+    pub vector: Vector4<f32>,
+    /// Already scaled by the intensity
+    pub color: Vector3<f32>,
+    /// 0 for directional and 1 for point
+    pub kind: u32,
+    pub shadow_hardness: f32,
+    pub _padding: [f32; 3],
+}
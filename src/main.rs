@@ -1,77 +1,118 @@
-use crate::camera::{Camera, GpuCamera};
-use bytemuck::NoUninit;
 use eframe::{egui, egui_wgpu::WgpuSetupCreateNew, wgpu};
 use math::{Rotor, Vector3, Vector4};
+use raytracing::{
+    animation::{self, Keyframe, Timeline},
+    camera::{Bookmark, GpuCamera, ViewMode},
+    cpu::Image,
+    gizmo,
+    history::{Edit, History, ListEdit},
+    lights::{GpuLight, Light, LightKind},
+    objects::{GpuSphere, GpuWormhole, ObjectsInfo, Sphere, Wormhole, wormhole_sdf},
+    path_tracing::{GpuFrame, PathTracer},
+    physics::{self, Simulation},
+    picking::{self, Object, Pick},
+    render_scale::{self, Filter, RenderScale},
+    scene::Scene,
+    screenshot::{self, ScreenshotError},
+    tonemapping::{Curve, GpuTonemapping},
+};
 use std::{sync::Arc, time::Instant};
 
-pub mod camera;
-pub mod sdf;
+/// Storage key for the scene that was open when the app was last closed, in the scene file format
+/// so that sessions from older versions are migrated the same way as scene files
+const SCENE_KEY: &str = "scene";
+const SCENE_PATH_KEY: &str = "scene_path";
+const SCREENSHOT_PATH_KEY: &str = "screenshot_path";
 
-#[derive(Debug, Clone, Copy, NoUninit)]
-#[repr(C)]
-struct ObjectsInfo {
-    wormholes_count: u32,
-    spheres_count: u32,
-}
-
-#[derive(Debug, Clone, Copy, NoUninit)]
-#[repr(C)]
-struct Wormhole {
-    position: Vector3<f32>,
-    throat_size: f32,
-}
-
-#[derive(Debug)]
-struct Sphere {
-    position: Vector4<f32>,
-    rotation: Rotor,
-}
-
-#[derive(Debug, Clone, Copy, NoUninit)]
-#[repr(C)]
-struct GpuSphere {
-    position: Vector4<f32>,
-    forward: Vector4<f32>,
-    up: Vector4<f32>,
-    right: Vector4<f32>,
-    ana: Vector4<f32>,
-}
+/// The planes in the order of [`Rotor::plane_angles`]
+const PLANE_NAMES: [&str; 6] = ["xy", "xz", "xw", "yz", "yw", "zw"];
+/// How far the buttons next to each plane angle rotate a sphere
+const ROTATION_STEP: f32 = std::f32::consts::TAU / 24.0;
+/// The largest width or height of a high resolution image, which is a 1 GiB image when it is both
+const MAX_HIGH_RESOLUTION: u32 = 16384;
 
 struct App {
     last_time: Option<Instant>,
 
+    scene: Scene,
+    scene_path: String,
+    scene_status: Option<String>,
+    screenshot_path: String,
+    screenshot_status: Option<String>,
+    /// Read back the output texture once this frame has been drawn
+    save_screenshot: bool,
+    /// The size to render at for [`App::render_tiled`]
+    high_resolution_width: u32,
+    high_resolution_height: u32,
+    save_high_resolution: bool,
+    /// How many frames are averaged for each tile of a high resolution image when the view
+    /// accumulates
+    high_resolution_frames: u32,
+    timeline: Timeline,
+    export_frame_rate: f32,
+    /// The next frame of the camera path to save, while it is being exported
+    export_frame: Option<u32>,
+    simulation: Simulation,
+    path_tracer: PathTracer,
+    render_scale: RenderScale,
+    history: History,
+    selection: Option<Pick>,
+    /// Open the selected object in its window, set when it has just been picked
+    reveal_selection: bool,
+
     output_texture_bind_group_layout: wgpu::BindGroupLayout,
+    tonemap_bind_group_layout: wgpu::BindGroupLayout,
 
     output_texture_width: u32,
     output_texture_height: u32,
-    output_texture: wgpu::TextureView,
+    output_texture: wgpu::Texture,
+    output_texture_view: wgpu::TextureView,
     output_texture_id: egui::TextureId,
+    /// The filter that the output texture was last registered with
+    output_texture_filter: Filter,
     output_texture_bind_group: wgpu::BindGroup,
 
-    camera: Camera,
+    tonemapping_buffer: wgpu::Buffer,
+    tonemap_bind_group: wgpu::BindGroup,
+
     camera_buffer: wgpu::Buffer,
+    frame_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
 
     objects_info_buffer: wgpu::Buffer,
 
-    wormholes: Vec<Wormhole>,
+    sheets_buffer: wgpu::Buffer,
+
     wormholes_buffer: wgpu::Buffer,
 
-    spheres: Vec<Sphere>,
     spheres_buffer: wgpu::Buffer,
 
+    lights_buffer: wgpu::Buffer,
+
     objects_bind_group_layout: wgpu::BindGroupLayout,
     objects_bind_group: wgpu::BindGroup,
 
     ray_tracing_pipeline: wgpu::ComputePipeline,
+    path_tracing_pipeline: wgpu::ComputePipeline,
+    surface_ray_tracing_pipeline: wgpu::ComputePipeline,
+    tonemap_pipeline: wgpu::ComputePipeline,
 }
 
-fn output_texture_and_bind_group(
+/// The textures that are rendered into, and the bind groups for rendering into them and then
+/// tonemapping what was rendered into the output texture
+fn output_texture_and_bind_groups(
     device: &wgpu::Device,
     layout: &wgpu::BindGroupLayout,
+    tonemap_layout: &wgpu::BindGroupLayout,
+    tonemapping_buffer: &wgpu::Buffer,
     width: u32,
     height: u32,
-) -> (wgpu::TextureView, wgpu::BindGroup) {
+) -> (
+    wgpu::Texture,
+    wgpu::TextureView,
+    wgpu::BindGroup,
+    wgpu::BindGroup,
+) {
     let texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Output Texture"),
         size: wgpu::Extent3d {
@@ -82,28 +123,93 @@ fn output_texture_and_bind_group(
         mip_level_count: 1,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Bgra8Unorm,
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        // copied from for screenshots
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     });
     let texture_view = texture.create_view(&Default::default());
 
+    let hdr_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("HDR Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba16Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+        view_formats: &[],
+    });
+    let hdr_texture_view = hdr_texture.create_view(&Default::default());
+
+    let accumulation_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Accumulation Buffer"),
+        size: width as u64 * height as u64 * size_of::<[f32; 4]>() as u64,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+
     let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Texture Bind Group"),
         layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: wgpu::BindingResource::TextureView(&texture_view),
-        }],
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&hdr_texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: accumulation_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    let tonemap_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Tonemap Bind Group"),
+        layout: tonemap_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&hdr_texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: tonemapping_buffer.as_entire_binding(),
+            },
+        ],
     });
 
-    (texture_view, texture_bind_group)
+    (
+        texture,
+        texture_view,
+        texture_bind_group,
+        tonemap_bind_group,
+    )
+}
+
+fn sheets_buffer(device: &wgpu::Device, count: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Sheets Buffer"),
+        size: (count.max(1) * size_of::<f32>()) as _,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
 }
 
 fn wormholes_buffer(device: &wgpu::Device, count: usize) -> wgpu::Buffer {
     device.create_buffer(&wgpu::BufferDescriptor {
         label: Some("Wormholes Buffer"),
-        size: (count.max(1) * size_of::<Wormhole>()) as _,
+        size: (count.max(1) * size_of::<GpuWormhole>()) as _,
         usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         mapped_at_creation: false,
     })
@@ -118,12 +224,23 @@ fn spheres_buffer(device: &wgpu::Device, count: usize) -> wgpu::Buffer {
     })
 }
 
+fn lights_buffer(device: &wgpu::Device, count: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Lights Buffer"),
+        size: (count.max(1) * size_of::<GpuLight>()) as _,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
 fn objects_bind_group(
     device: &wgpu::Device,
     layout: &wgpu::BindGroupLayout,
     objects_info_buffer: &wgpu::Buffer,
     wormholes_buffer: &wgpu::Buffer,
     spheres_buffer: &wgpu::Buffer,
+    sheets_buffer: &wgpu::Buffer,
+    lights_buffer: &wgpu::Buffer,
 ) -> wgpu::BindGroup {
     device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Objects Bind Group"),
@@ -141,10 +258,142 @@ fn objects_bind_group(
                 binding: 2,
                 resource: spheres_buffer.as_entire_binding(),
             },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: sheets_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: lights_buffer.as_entire_binding(),
+            },
         ],
     })
 }
 
+/// The header of an object in its window, highlighted if it is selected and opened if it has just been picked
+fn object_header(
+    ui: &egui::Ui,
+    name: &str,
+    selected: bool,
+    reveal: bool,
+) -> egui::CollapsingHeader {
+    let mut text = egui::RichText::new(name);
+    if selected {
+        text = text.strong().color(ui.visuals().selection.stroke.color);
+    }
+    egui::CollapsingHeader::new(text).open((selected && reveal).then_some(true))
+}
+
+/// Whether the object that was picked is still in the scene, it might not be after an undo or redo
+fn selection_exists(scene: &Scene, pick: &Pick) -> bool {
+    match pick.object {
+        Some(Object::Sphere(i)) => i < scene.spheres.len(),
+        Some(Object::Wormhole(i)) => i < scene.wormholes.len(),
+        None => true,
+    }
+}
+
+/// Keeps the selection pointing at the same object once `removed` has been deleted
+fn remove_from_selection(selection: &mut Option<Pick>, removed: Object) {
+    if let Some(pick) = selection
+        && let Some(object) = pick.object
+    {
+        match object.after_removing(removed) {
+            Some(object) => pick.object = Some(object),
+            None => *selection = None,
+        }
+    }
+}
+
+/// Outlines the selected object over the viewport in `rect`, and marks the point that was picked
+fn draw_selection(painter: &egui::Painter, rect: egui::Rect, scene: &Scene, pick: Pick) {
+    let camera = scene.gpu_camera();
+    let stroke = egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 200, 0));
+    let to_screen = |p| {
+        picking::screen_position(&camera, p, rect.width(), rect.height())
+            .map(|[x, y]| egui::pos2(rect.left() + x, rect.bottom() - y))
+    };
+
+    match pick.object {
+        Some(Object::Sphere(i)) => {
+            let sphere = &scene.spheres[i];
+            if let Some(centre) = to_screen(sphere.position)
+                && let Some(edge) = to_screen(sphere.position + camera.up * sphere.radius)
+            {
+                painter.circle_stroke(centre, centre.distance(edge), stroke);
+            }
+        }
+        Some(Object::Wormhole(i)) => {
+            let wormhole = &scene.wormholes[i];
+            if let Some(sheet_distance) = wormhole.sheet_distance(&scene.sheets) {
+                // the rim of each mouth, drawn in the sheet around the direction the camera is looking
+                let radius = wormhole.throat_size
+                    + wormhole.throat_length(scene.throat_length, sheet_distance);
+                let forward = Vector3 {
+                    x: camera.forward.x,
+                    y: camera.forward.y,
+                    z: camera.forward.z,
+                };
+                let right = Vector3 {
+                    x: camera.right.x,
+                    y: camera.right.y,
+                    z: camera.right.z,
+                };
+                let (a, b) = if forward.square_magnitude() > 0.001 {
+                    let a = forward.normalised();
+                    (a, (right - a * right.dot(a)).normalised())
+                } else {
+                    (
+                        Vector3 {
+                            x: 1.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                        Vector3 {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 1.0,
+                        },
+                    )
+                };
+
+                let mouths = [
+                    (wormhole.position, scene.sheets[wormhole.sheet]),
+                    (wormhole.other_position(), scene.sheets[wormhole.sheet + 1]),
+                ];
+                for (mouth, w) in mouths {
+                    let points = (0..64)
+                        .map(|i| {
+                            let angle = i as f32 / 64.0 * std::f32::consts::TAU;
+                            let p = mouth + (a * angle.cos() + b * angle.sin()) * radius;
+                            to_screen(Vector4 {
+                                x: p.x,
+                                y: p.y,
+                                z: p.z,
+                                w,
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>();
+                    if let Some(points) = points {
+                        painter.add(egui::Shape::closed_line(points, stroke));
+                    }
+                }
+            }
+        }
+        None => {}
+    }
+
+    if let Some(position) = to_screen(pick.position) {
+        painter.circle_filled(position, 3.0, stroke.color);
+    }
+}
+
+/// Which sheet the camera is closest to, for showing in the UI
+fn camera_sheet(scene: &Scene) -> Option<String> {
+    let sheet = scene.nearest_sheet(scene.camera.position.w)?;
+    Some(format!("Sheet: {sheet} (w = {})", scene.sheets[sheet]))
+}
+
 impl App {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let eframe::egui_wgpu::RenderState {
@@ -156,62 +405,152 @@ impl App {
         let output_texture_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Output Texture Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Bgra8Unorm,
-                        view_dimension: wgpu::TextureViewDimension::D2,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::StorageTexture {
+                            access: wgpu::StorageTextureAccess::WriteOnly,
+                            format: wgpu::TextureFormat::Rgba16Float,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                ],
             });
-        let (output_texture, output_texture_bind_group) = output_texture_and_bind_group(
-            device,
-            &output_texture_bind_group_layout,
-            output_texture_width,
-            output_texture_height,
-        );
+        let tonemap_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Tonemap Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::StorageTexture {
+                            access: wgpu::StorageTextureAccess::WriteOnly,
+                            format: wgpu::TextureFormat::Rgba8Unorm,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let tonemapping_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Tonemapping Buffer"),
+            size: size_of::<GpuTonemapping>().next_multiple_of(16) as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (output_texture, output_texture_view, output_texture_bind_group, tonemap_bind_group) =
+            output_texture_and_bind_groups(
+                device,
+                &output_texture_bind_group_layout,
+                &tonemap_bind_group_layout,
+                &tonemapping_buffer,
+                output_texture_width,
+                output_texture_height,
+            );
+        let output_texture_filter = Filter::default();
         let output_texture_id = renderer.write().register_native_texture(
             device,
-            &output_texture,
-            wgpu::FilterMode::Nearest,
+            &output_texture_view,
+            output_texture_filter.to_wgpu(),
         );
 
-        let camera = Camera::new(Vector4 {
-            x: -3.0,
-            y: 0.0,
-            z: 0.0,
-            w: 2.0,
-        });
+        let scene = cc
+            .storage
+            .and_then(|storage| storage.get_string(SCENE_KEY))
+            .and_then(|json| Scene::from_json(&json).ok())
+            .unwrap_or_default();
+        let scene_path = cc
+            .storage
+            .and_then(|storage| storage.get_string(SCENE_PATH_KEY))
+            .unwrap_or_else(|| "scene.json".into());
+        let screenshot_path = cc
+            .storage
+            .and_then(|storage| storage.get_string(SCREENSHOT_PATH_KEY))
+            .unwrap_or_else(|| "screenshot.png".into());
+
         let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Camera Buffer"),
             size: size_of::<GpuCamera>().next_multiple_of(16) as _,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
+        let frame_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Buffer"),
+            size: size_of::<GpuFrame>().next_multiple_of(16) as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Camera Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                ],
             });
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Camera Bind Group"),
             layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: frame_buffer.as_entire_binding(),
+                },
+            ],
         });
 
         let objects_info_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -221,26 +560,10 @@ impl App {
             mapped_at_creation: false,
         });
 
-        let wormholes = vec![Wormhole {
-            position: Vector3 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            throat_size: 3.0,
-        }];
-        let wormholes_buffer = wormholes_buffer(device, wormholes.len());
-
-        let spheres = vec![Sphere {
-            position: Vector4 {
-                x: 8.0,
-                y: 0.0,
-                z: 0.0,
-                w: 6.0,
-            },
-            rotation: Rotor::identity(),
-        }];
-        let spheres_buffer = spheres_buffer(device, spheres.len());
+        let sheets_buffer = sheets_buffer(device, scene.sheets.len());
+        let wormholes_buffer = wormholes_buffer(device, scene.wormholes.len());
+        let spheres_buffer = spheres_buffer(device, scene.spheres.len());
+        let lights_buffer = lights_buffer(device, scene.lights.len());
 
         let objects_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -276,6 +599,26 @@ impl App {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
         let objects_bind_group = objects_bind_group(
@@ -284,6 +627,8 @@ impl App {
             &objects_info_buffer,
             &wormholes_buffer,
             &spheres_buffer,
+            &sheets_buffer,
+            &lights_buffer,
         );
 
         let ray_tracing_shader = device.create_shader_module(wgpu::include_wgsl!(concat!(
@@ -309,90 +654,241 @@ impl App {
                 compilation_options: Default::default(),
                 cache: None,
             });
+        let path_tracing_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Path Tracing Pipeline"),
+                layout: Some(&ray_tracing_pipeline_layout),
+                module: &ray_tracing_shader,
+                entry_point: Some("path_trace_rays"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+        let surface_ray_tracing_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Surface Ray Tracing Pipeline"),
+                layout: Some(&ray_tracing_pipeline_layout),
+                module: &ray_tracing_shader,
+                entry_point: Some("trace_surface_rays"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+
+        let tonemap_shader = device.create_shader_module(wgpu::include_wgsl!(concat!(
+            env!("OUT_DIR"),
+            "/shaders/tonemap.wgsl"
+        )));
+        let tonemap_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Tonemap Pipeline Layout"),
+                bind_group_layouts: &[&tonemap_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let tonemap_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Tonemap Pipeline"),
+            layout: Some(&tonemap_pipeline_layout),
+            module: &tonemap_shader,
+            entry_point: Some("tonemap"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
 
         Self {
             last_time: None,
 
+            scene,
+            scene_path,
+            scene_status: None,
+            screenshot_path,
+            screenshot_status: None,
+            save_screenshot: false,
+            high_resolution_width: 7680,
+            high_resolution_height: 4320,
+            save_high_resolution: false,
+            high_resolution_frames: 64,
+            timeline: Timeline::default(),
+            export_frame_rate: 30.0,
+            export_frame: None,
+            simulation: Simulation::default(),
+            path_tracer: PathTracer::default(),
+            render_scale: RenderScale::default(),
+            history: History::default(),
+            selection: None,
+            reveal_selection: false,
+
             output_texture_bind_group_layout,
+            tonemap_bind_group_layout,
 
             output_texture_width,
             output_texture_height,
             output_texture,
+            output_texture_view,
             output_texture_id,
+            output_texture_filter,
             output_texture_bind_group,
 
-            camera,
+            tonemapping_buffer,
+            tonemap_bind_group,
+
             camera_buffer,
+            frame_buffer,
             camera_bind_group,
 
             objects_info_buffer,
 
-            wormholes,
+            sheets_buffer,
+
             wormholes_buffer,
 
-            spheres,
             spheres_buffer,
 
+            lights_buffer,
+
             objects_bind_group_layout,
             objects_bind_group,
 
             ray_tracing_pipeline,
+            path_tracing_pipeline,
+            surface_ray_tracing_pipeline,
+            tonemap_pipeline,
         }
     }
 
-    fn wormhole_sdf(wormholes: &[Wormhole], p: Vector4<f32>) -> f32 {
-        let throat_length = 4.0;
-        let plane = f32::abs(p.w) - throat_length;
-
-        let mut d = plane;
-        for wormhole in wormholes {
-            let cylinder = (Vector3 {
-                x: p.x,
-                y: p.y,
-                z: p.z,
-            } - wormhole.position)
-                .magnitude()
-                - (wormhole.throat_size + throat_length);
-            d = f32::max(d, -cylinder);
+    /// Records tracing the rays for a `width` by `height` texture and then tonemapping it
+    fn encode_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture_bind_group: &wgpu::BindGroup,
+        tonemap_bind_group: &wgpu::BindGroup,
+        width: u32,
+        height: u32,
+    ) {
+        self.encode_ray_tracing_pass(encoder, texture_bind_group, width, height);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Tonemap Pass"),
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(&self.tonemap_pipeline);
+            compute_pass.set_bind_group(0, tonemap_bind_group, &[]);
+            compute_pass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
         }
-        for wormhole in wormholes {
-            let torus = sdf::torus(
-                p - Vector4 {
-                    x: wormhole.position.x,
-                    y: wormhole.position.y,
-                    z: wormhole.position.z,
-                    w: 0.0,
-                },
-                wormhole.throat_size + throat_length,
-                throat_length,
-            );
-            d = f32::min(d, torus);
+    }
+
+    /// Records tracing the rays for a `width` by `height` texture, without tonemapping it
+    fn encode_ray_tracing_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture_bind_group: &wgpu::BindGroup,
+        width: u32,
+        height: u32,
+    ) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Compute Pass"),
+            timestamp_writes: None,
+        });
+
+        compute_pass.set_pipeline(match self.scene.camera.view_mode {
+            ViewMode::Embedded if self.path_tracer.enabled => &self.path_tracing_pipeline,
+            ViewMode::Embedded => &self.ray_tracing_pipeline,
+            ViewMode::Surface => &self.surface_ray_tracing_pipeline,
+        });
+        compute_pass.set_bind_group(0, texture_bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.camera_bind_group, &[]);
+        compute_pass.set_bind_group(2, &self.objects_bind_group, &[]);
+        compute_pass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
+    }
+
+    /// Whether the frames of the view are averaged together, so that a still image gets better
+    /// the more frames are rendered
+    fn accumulates(&self) -> bool {
+        match self.scene.camera.view_mode {
+            ViewMode::Embedded if self.path_tracer.enabled => true,
+            _ => self.scene.camera.accumulate,
         }
-        d
     }
 
-    fn project_spheres(&mut self) {
-        for sphere in &mut self.spheres {
-            {
-                let distance = Self::wormhole_sdf(&self.wormholes, sphere.position);
-                if f32::abs(distance) > 0.0001 {
-                    let normal =
-                        sdf::normal(|p| Self::wormhole_sdf(&self.wormholes, p), sphere.position);
-                    sphere.position -= normal * distance;
-                }
-            }
+    /// Renders a single `width` by `height` image of the view, independent of the size of the
+    /// output texture, a tile at a time so that no dispatch runs for long enough to time out
+    ///
+    /// When the view accumulates, each tile is the average of [`App::high_resolution_frames`]
+    /// frames, the same as leaving the view still for that long
+    fn render_tiled(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+    ) -> Result<Image, ScreenshotError> {
+        let (texture, _, texture_bind_group, tonemap_bind_group) = output_texture_and_bind_groups(
+            device,
+            &self.output_texture_bind_group_layout,
+            &self.tonemap_bind_group_layout,
+            &self.tonemapping_buffer,
+            screenshot::TILE_SIZE,
+            screenshot::TILE_SIZE,
+        );
 
-            {
-                let normal =
-                    sdf::normal(|p| Self::wormhole_sdf(&self.wormholes, p), sphere.position);
-                if normal.square_magnitude() > 0.0 {
-                    let old_normal = sphere.rotation.w();
-                    let correction_rotation =
-                        Rotor::from_to_vector(old_normal, normal * old_normal.dot(normal).signum());
-                    sphere.rotation = correction_rotation.then(sphere.rotation).normalised();
+        let len = width as usize * height as usize;
+        let mut pixels = Vec::new();
+        pixels
+            .try_reserve_exact(len)
+            .map_err(ScreenshotError::OutOfMemory)?;
+        pixels.resize(len, [0; 4]);
+        let mut image = Image {
+            width,
+            height,
+            pixels,
+        };
+
+        let frames = if self.accumulates() {
+            self.high_resolution_frames.max(1)
+        } else {
+            1
+        };
+        for tile in screenshot::tiles(width, height) {
+            for index in 0..frames {
+                // the view's frame has already been submitted, and the frame buffer is written
+                // again before the next one
+                let frame = GpuFrame {
+                    index,
+                    max_bounces: self.path_tracer.max_bounces,
+                    offset: [tile.x, tile.y],
+                    size: [width, height],
+                };
+                queue.write_buffer(&self.frame_buffer, 0, bytemuck::bytes_of(&frame));
+
+                // a submission for each frame, so that none of them runs for long enough to time
+                // out either
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Tile Command Encoder"),
+                });
+                if index + 1 < frames {
+                    self.encode_ray_tracing_pass(
+                        &mut encoder,
+                        &texture_bind_group,
+                        tile.width,
+                        tile.height,
+                    );
+                } else {
+                    self.encode_passes(
+                        &mut encoder,
+                        &texture_bind_group,
+                        &tonemap_bind_group,
+                        tile.width,
+                        tile.height,
+                    );
                 }
+                queue.submit(core::iter::once(encoder.finish()));
             }
+
+            image.paste(
+                &screenshot::read_texture(device, queue, &texture)?,
+                tile.x,
+                tile.y,
+            );
         }
+        Ok(image)
     }
 }
 
@@ -408,33 +904,503 @@ impl eframe::App for App {
         let time = Instant::now();
         let dt = time - self.last_time.unwrap_or(time);
         self.last_time = Some(time);
+        self.render_scale.update(dt.as_secs_f32());
+
+        if self.history.update(ctx, &mut self.scene) {
+            self.selection = self
+                .selection
+                .filter(|pick| selection_exists(&self.scene, pick));
+        }
 
         egui::Window::new("Camera")
             .resizable(false)
             .show(ctx, |ui| {
                 ui.label(format!("FPS: {:.3}", 1.0 / dt.as_secs_f32()));
-                self.camera.ui(ui);
-            });
-
-        egui::Window::new("Wormholes")
-            .resizable(false)
-            .show(ctx, |ui| {
-                if ui.button("New Wormhole").clicked() {
-                    self.wormholes.push(Wormhole {
-                        position: Vector3 {
-                            x: 0.0,
-                            y: 0.0,
-                            z: 0.0,
-                        },
-                        throat_size: 3.0,
+                if let Some(sheet) = camera_sheet(&self.scene) {
+                    ui.label(sheet);
+                }
+                self.scene.camera.ui(ui);
+
+                ui.collapsing("Bookmarks", |ui| {
+                    if ui.button("Add Bookmark").clicked() {
+                        let index = self.scene.bookmarks.len();
+                        let item =
+                            Bookmark::new(format!("Bookmark {}", index + 1), &self.scene.camera);
+                        self.history.push(
+                            ctx,
+                            Edit::Bookmark(ListEdit::Add {
+                                index,
+                                item: item.clone(),
+                            }),
+                        );
+                        self.scene.bookmarks.push(item);
+                    }
+
+                    let mut to_delete = None;
+                    for (i, bookmark) in self.scene.bookmarks.iter_mut().enumerate() {
+                        let before = bookmark.clone();
+                        ui.push_id(i, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut bookmark.name)
+                                        .desired_width(120.0),
+                                );
+                                if ui.button("Go To").clicked() {
+                                    bookmark.go_to(&mut self.scene.camera);
+                                }
+                                if ui
+                                    .button("Update")
+                                    .on_hover_text("Move the bookmark to where the camera is now")
+                                    .clicked()
+                                {
+                                    *bookmark = Bookmark::new(
+                                        std::mem::take(&mut bookmark.name),
+                                        &self.scene.camera,
+                                    );
+                                }
+                                if ui.button("Delete").clicked() {
+                                    to_delete = Some(i);
+                                }
+                            });
+                        });
+                        if *bookmark != before {
+                            let edit = ListEdit::Change {
+                                index: i,
+                                before,
+                                after: bookmark.clone(),
+                            };
+                            self.history.push(ctx, Edit::Bookmark(edit));
+                        }
+                    }
+                    if let Some(i) = to_delete {
+                        let item = self.scene.bookmarks.remove(i);
+                        self.history
+                            .push(ctx, Edit::Bookmark(ListEdit::Remove { index: i, item }));
+                    }
+                });
+            });
+
+        egui::Window::new("Rendering")
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("Tonemapping Grid").show(ui, |ui| {
+                    let tonemapping = &mut self.scene.tonemapping;
+
+                    ui.label("Exposure:");
+                    ui.add(
+                        egui::DragValue::new(&mut tonemapping.exposure)
+                            .speed(0.05)
+                            .suffix(" stops"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Curve:").on_hover_text(
+                        "How colors brighter than white are brought back into range",
+                    );
+                    ui.horizontal(|ui| {
+                        for curve in Curve::ALL {
+                            ui.selectable_value(&mut tonemapping.curve, curve, curve.name());
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("sRGB Output:").on_hover_text(
+                        "Encode the colors for the screen, instead of showing them as they are rendered",
+                    );
+                    ui.checkbox(&mut tonemapping.srgb, "");
+                    ui.end_row();
+                });
+
+                ui.separator();
+
+                egui::Grid::new("Render Scale Grid").show(ui, |ui| {
+                    let render_scale = &mut self.render_scale;
+
+                    ui.label("Render Scale:").on_hover_text(
+                        "The resolution that the view is rendered at, relative to the screen",
+                    );
+                    ui.add_enabled(
+                        !render_scale.automatic,
+                        egui::Slider::new(
+                            &mut render_scale.scale,
+                            render_scale::MIN_SCALE..=render_scale::MAX_SCALE,
+                        )
+                        .suffix("x"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Automatic:").on_hover_text(
+                        "Lower or raise the render scale to keep the frame rate at the target",
+                    );
+                    ui.checkbox(&mut render_scale.automatic, "");
+                    ui.end_row();
+
+                    if render_scale.automatic {
+                        ui.label("Target FPS:");
+                        ui.add(
+                            egui::DragValue::new(&mut render_scale.target_fps)
+                                .range(1.0..=240.0),
+                        );
+                        ui.end_row();
+                    }
+
+                    ui.label("Filter:").on_hover_text(
+                        "How the rendered image is stretched over the view",
+                    );
+                    ui.horizontal(|ui| {
+                        for filter in Filter::ALL {
+                            ui.selectable_value(&mut render_scale.filter, filter, filter.name());
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Resolution:");
+                    ui.label(format!(
+                        "{}x{}",
+                        self.output_texture_width, self.output_texture_height
+                    ));
+                    ui.end_row();
+                });
+
+                ui.separator();
+
+                ui.checkbox(&mut self.path_tracer.enabled, "Path Tracing").on_hover_text(
+                    "Keep adding samples to each pixel while nothing changes, with light bouncing between surfaces",
+                );
+                if self.path_tracer.enabled {
+                    if self.scene.camera.view_mode == ViewMode::Surface {
+                        ui.label("The surface view isn't path traced");
+                    }
+
+                    egui::Grid::new("Path Tracing Grid").show(ui, |ui| {
+                        ui.label("Max Bounces:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.path_tracer.max_bounces).range(0..=32),
+                        );
+                        ui.end_row();
                     });
+
+                    ui.horizontal(|ui| {
+                        let samples =
+                            self.path_tracer.frames() * self.scene.camera.samples_per_pixel;
+                        ui.label(format!("Samples: {samples}"));
+                        if ui.button("Restart").clicked() {
+                            self.path_tracer.restart();
+                        }
+                    });
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Screenshot Path:");
+                    ui.text_edit_singleline(&mut self.screenshot_path);
+                });
+                if ui
+                    .button("Save Screenshot")
+                    .on_hover_text("Save the view as a png, with the scene in its metadata")
+                    .clicked()
+                {
+                    self.save_screenshot = true;
                 }
 
+                let accumulates = self.accumulates();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.high_resolution_width)
+                            .range(1..=MAX_HIGH_RESOLUTION)
+                            .suffix(" px"),
+                    );
+                    ui.label("x");
+                    ui.add(
+                        egui::DragValue::new(&mut self.high_resolution_height)
+                            .range(1..=MAX_HIGH_RESOLUTION)
+                            .suffix(" px"),
+                    );
+                    ui.add_enabled(
+                        accumulates,
+                        egui::DragValue::new(&mut self.high_resolution_frames)
+                            .range(1..=4096)
+                            .suffix(" frames"),
+                    )
+                    .on_hover_text("How many frames are averaged for each tile");
+                    if ui
+                        .button("Save High Resolution")
+                        .on_hover_text(
+                            "Render the view at this size in tiles, and save it to the screenshot path",
+                        )
+                        .clicked()
+                    {
+                        self.save_high_resolution = true;
+                    }
+                });
+                if let Some(status) = &self.screenshot_status {
+                    ui.label(status);
+                }
+            });
+
+        egui::Window::new("Timeline")
+            .resizable(false)
+            .show(ctx, |ui| {
+                let duration = animation::duration(&self.scene.keyframes);
+                let camera = &mut self.scene.camera;
+
+                ui.horizontal(|ui| {
+                    let label = if self.timeline.playing { "Pause" } else { "Play" };
+                    if ui.button(label).clicked() {
+                        if !self.timeline.playing && self.timeline.time >= duration {
+                            self.timeline.time = 0.0;
+                        }
+                        self.timeline.playing = !self.timeline.playing;
+                    }
+                    ui.checkbox(&mut self.timeline.looping, "Loop");
+                });
+                if ui
+                    .add(egui::Slider::new(&mut self.timeline.time, 0.0..=duration).suffix(" s"))
+                    .changed()
+                    && let Some(keyframe) =
+                        animation::sample(&self.scene.keyframes, self.timeline.time)
+                {
+                    keyframe.apply(camera);
+                }
+
+                if ui
+                    .button("Add Keyframe")
+                    .on_hover_text("Add where the camera is now at the current time")
+                    .clicked()
+                {
+                    let index = self.scene.keyframes.len();
+                    let item = Keyframe::new(self.timeline.time, camera);
+                    self.history.push(
+                        ctx,
+                        Edit::Keyframe(ListEdit::Add {
+                            index,
+                            item: item.clone(),
+                        }),
+                    );
+                    self.scene.keyframes.push(item);
+                }
+
+                let mut to_delete = None;
+                for (i, keyframe) in self.scene.keyframes.iter_mut().enumerate() {
+                    let before = keyframe.clone();
+                    ui.push_id(i, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut keyframe.time)
+                                    .speed(0.05)
+                                    .range(0.0..=f32::INFINITY)
+                                    .suffix(" s"),
+                            );
+                            if ui.button("Go To").clicked() {
+                                self.timeline.time = keyframe.time;
+                                keyframe.apply(camera);
+                            }
+                            if ui
+                                .button("Update")
+                                .on_hover_text("Move the keyframe to where the camera is now")
+                                .clicked()
+                            {
+                                *keyframe = Keyframe::new(keyframe.time, camera);
+                            }
+                            if ui.button("Delete").clicked() {
+                                to_delete = Some(i);
+                            }
+                        });
+                    });
+                    if *keyframe != before {
+                        let edit = ListEdit::Change {
+                            index: i,
+                            before,
+                            after: keyframe.clone(),
+                        };
+                        self.history.push(ctx, Edit::Keyframe(edit));
+                    }
+                }
+                if let Some(i) = to_delete {
+                    let item = self.scene.keyframes.remove(i);
+                    self.history
+                        .push(ctx, Edit::Keyframe(ListEdit::Remove { index: i, item }));
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.label("Frame Rate:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.export_frame_rate)
+                            .range(1.0..=240.0)
+                            .suffix(" fps"),
+                    );
+                    if let Some(frame) = self.export_frame {
+                        ui.label(format!("Exporting frame {frame}"));
+                        if ui.button("Cancel").clicked() {
+                            self.export_frame = None;
+                        }
+                    } else if ui
+                        .add_enabled(
+                            !self.scene.keyframes.is_empty(),
+                            egui::Button::new("Export Frames"),
+                        )
+                        .on_hover_text(
+                            "Save each frame of the camera path as a numbered png at the screenshot path",
+                        )
+                        .clicked()
+                    {
+                        self.export_frame = Some(0);
+                        self.timeline.playing = false;
+                    }
+                });
+            });
+
+        egui::Window::new("Scene").resizable(false).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Path:");
+                ui.text_edit_singleline(&mut self.scene_path);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Open").clicked() {
+                    self.scene_status = Some(match Scene::load(&self.scene_path) {
+                        Ok(scene) => {
+                            self.scene = scene;
+                            self.history.clear();
+                            self.selection = None;
+                            format!("Opened {}", self.scene_path)
+                        }
+                        Err(error) => format!("Failed to open {}: {error}", self.scene_path),
+                    });
+                }
+                if ui.button("Save").clicked() {
+                    self.scene_status = Some(match self.scene.save(&self.scene_path) {
+                        Ok(()) => format!("Saved {}", self.scene_path),
+                        Err(error) => format!("Failed to save {}: {error}", self.scene_path),
+                    });
+                }
+            });
+            ui.horizontal(|ui| {
+                let mut changed = false;
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    self.history.undo(&mut self.scene);
+                    changed = true;
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                    .on_hover_text("Ctrl+Shift+Z")
+                    .clicked()
+                {
+                    self.history.redo(&mut self.scene);
+                    changed = true;
+                }
+                if changed {
+                    self.selection = self
+                        .selection
+                        .filter(|pick| selection_exists(&self.scene, pick));
+                }
+            });
+            if let Some(status) = &self.scene_status {
+                ui.label(status);
+            }
+        });
+
+        egui::Window::new("Sheets")
+            .resizable(false)
+            .show(ctx, |ui| {
+                if ui.button("New Sheet").clicked() {
+                    // as far above the top sheet as that is above the one below it
+                    let w = match self.scene.sheets[..] {
+                        [.., below, top] => top + (top - below),
+                        [top] => top + 8.0,
+                        [] => 0.0,
+                    };
+                    self.scene.sheets.push(w);
+                }
+
+                let sheets_count = self.scene.sheets.len();
+                let mut to_delete = None;
+                egui::Grid::new("Sheets Grid").show(ui, |ui| {
+                    for i in 0..sheets_count {
+                        // keep the sheets in order
+                        let min = i
+                            .checked_sub(1)
+                            .map_or(f32::NEG_INFINITY, |below| self.scene.sheets[below] + 0.01);
+                        let max = self
+                            .scene
+                            .sheets
+                            .get(i + 1)
+                            .map_or(f32::INFINITY, |above| above - 0.01);
+
+                        ui.label(format!("Sheet {i}:"));
+                        ui.add(
+                            egui::DragValue::new(&mut self.scene.sheets[i])
+                                .prefix("w:")
+                                .speed(0.1)
+                                .range(min..=max),
+                        );
+                        if ui
+                            .add_enabled(sheets_count > 2, egui::Button::new("Delete"))
+                            .clicked()
+                        {
+                            to_delete = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(i) = to_delete {
+                    self.scene.sheets.remove(i);
+                    for wormhole in &mut self.scene.wormholes {
+                        if wormhole.sheet > i {
+                            wormhole.sheet -= 1;
+                        }
+                        wormhole.sheet = wormhole.sheet.min(self.scene.sheets.len() - 2);
+                    }
+                }
+            });
+
+        egui::Window::new("Wormholes")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Throat Length:").on_hover_text(
+                        "Radius of the curve where each throat meets its sheets, unless the wormhole overrides it",
+                    );
+                    ui.add(egui::DragValue::new(&mut self.scene.throat_length).speed(0.1));
+                    self.scene.throat_length = self.scene.throat_length.max(0.01);
+                });
+
+                if ui.button("New Wormhole").clicked() {
+                    let edit = ListEdit::Add {
+                        index: self.scene.wormholes.len(),
+                        item: Wormhole::default(),
+                    };
+                    self.history.push(ctx, Edit::Wormhole(edit));
+                    self.scene.wormholes.push(Wormhole::default());
+                }
+
+                let sheets = &self.scene.sheets;
+                let scene_throat_length = self.scene.throat_length;
+                let selected = self.selection.and_then(|pick| pick.object);
                 let mut to_delete = vec![];
-                for (i, wormhole) in self.wormholes.iter_mut().enumerate() {
+                for (i, wormhole) in self.scene.wormholes.iter_mut().enumerate() {
+                    let before = *wormhole;
                     ui.push_id(i, |ui| {
-                        ui.collapsing("Wormhole", |ui| {
+                        let selected = selected == Some(Object::Wormhole(i));
+                        let header = object_header(ui, "Wormhole", selected, self.reveal_selection);
+                        header.show(ui, |ui| {
                             egui::Grid::new("Wormhole Grid").show(ui, |ui| {
+                                ui.label("Sheets:").on_hover_text(
+                                    "The sheet that the mouth at Position is on, the wormhole connects it to the sheet above",
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut wormhole.sheet)
+                                        .range(0..=sheets.len().saturating_sub(2)),
+                                );
+                                ui.label(format!("to {}", wormhole.sheet + 1));
+                                ui.end_row();
+
                                 ui.label("Position:");
                                 ui.add(
                                     egui::DragValue::new(&mut wormhole.position.x)
@@ -453,20 +1419,237 @@ impl eframe::App for App {
                                 );
                                 ui.end_row();
 
+                                ui.label("Offset Mouths:").on_hover_text(
+                                    "Put the mouth on the upper sheet somewhere other than directly across from the mouth on the lower sheet",
+                                );
+                                let mut offset = wormhole.other_position.is_some();
+                                ui.checkbox(&mut offset, "");
+                                wormhole.other_position = offset.then(|| wormhole.other_position());
+                                ui.end_row();
+
+                                if let Some(other_position) = &mut wormhole.other_position {
+                                    ui.label("Other Position:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut other_position.x)
+                                            .prefix("x:")
+                                            .speed(0.1),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut other_position.y)
+                                            .prefix("y:")
+                                            .speed(0.1),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut other_position.z)
+                                            .prefix("z:")
+                                            .speed(0.1),
+                                    );
+                                    ui.end_row();
+                                }
+
                                 ui.label("Throat Size:");
                                 ui.add(egui::DragValue::new(&mut wormhole.throat_size).speed(0.1));
                                 wormhole.throat_size = wormhole.throat_size.max(0.0);
                                 ui.end_row();
 
+                                ui.label("Throat Length:").on_hover_text(
+                                    "Radius of the curve where the throat meets each sheet, at most half the distance between its sheets",
+                                );
+                                let sheet_distance = wormhole.sheet_distance(sheets).unwrap_or(0.0);
+                                let mut custom_throat_length = wormhole.throat_length.is_some();
+                                ui.checkbox(&mut custom_throat_length, "Custom");
+                                wormhole.throat_length = custom_throat_length.then(|| {
+                                    wormhole.throat_length(scene_throat_length, sheet_distance)
+                                });
+                                if let Some(throat_length) = &mut wormhole.throat_length {
+                                    ui.add(egui::DragValue::new(throat_length).speed(0.1));
+                                    *throat_length = throat_length.clamp(0.0, sheet_distance);
+                                }
+                                ui.end_row();
+
                                 if ui.button("Delete").clicked() {
                                     to_delete.push(i);
                                 }
                             });
                         });
                     });
+                    if *wormhole != before {
+                        let edit = ListEdit::Change {
+                            index: i,
+                            before,
+                            after: *wormhole,
+                        };
+                        self.history.push(ctx, Edit::Wormhole(edit));
+                    }
                 }
                 for i in to_delete.into_iter().rev() {
-                    self.wormholes.remove(i);
+                    let item = self.scene.wormholes.remove(i);
+                    self.history
+                        .push(ctx, Edit::Wormhole(ListEdit::Remove { index: i, item }));
+                    remove_from_selection(&mut self.selection, Object::Wormhole(i));
+                }
+            });
+
+        egui::Window::new("Simulation")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let label = if self.simulation.playing { "Pause" } else { "Play" };
+                    if ui.button(label).clicked() {
+                        self.simulation.playing = !self.simulation.playing;
+                    }
+                    if ui
+                        .add_enabled(!self.simulation.playing, egui::Button::new("Step"))
+                        .on_hover_text(format!("Advance the simulation by {}s", physics::TIMESTEP))
+                        .clicked()
+                    {
+                        physics::step(&mut self.scene);
+                    }
+                    if ui.button("Stop Spheres").clicked() {
+                        for sphere in &mut self.scene.spheres {
+                            sphere.velocity = Vector4 {
+                                x: 0.0,
+                                y: 0.0,
+                                z: 0.0,
+                                w: 0.0,
+                            };
+                        }
+                    }
+                });
+
+                egui::Grid::new("Simulation Grid").show(ui, |ui| {
+                    ui.label("Gravity:").on_hover_text(
+                        "Accelerates the spheres along the surface, so they slide down the throats by default",
+                    );
+                    let mut gravity = self.scene.gravity.is_some();
+                    ui.checkbox(&mut gravity, "");
+                    self.scene.gravity =
+                        gravity.then(|| self.scene.gravity.unwrap_or(physics::DEFAULT_GRAVITY));
+                    ui.end_row();
+
+                    if let Some(gravity) = &mut self.scene.gravity {
+                        ui.label("Acceleration:");
+                        ui.add(egui::DragValue::new(&mut gravity.x).prefix("x:").speed(0.1));
+                        ui.add(egui::DragValue::new(&mut gravity.y).prefix("y:").speed(0.1));
+                        ui.add(egui::DragValue::new(&mut gravity.z).prefix("z:").speed(0.1));
+                        ui.add(egui::DragValue::new(&mut gravity.w).prefix("w:").speed(0.1));
+                        ui.end_row();
+                    }
+                });
+            });
+
+        egui::Window::new("Lights")
+            .resizable(false)
+            .show(ctx, |ui| {
+                if self.scene.camera.view_mode == ViewMode::Surface {
+                    ui.label("The surface view isn't lit by these");
+                }
+
+                egui::Grid::new("Ambient Grid").show(ui, |ui| {
+                    ui.label("Ambient:")
+                        .on_hover_text("Light that reaches everywhere, even in shadow");
+                    ui.add(
+                        egui::DragValue::new(&mut self.scene.ambient)
+                            .speed(0.01)
+                            .range(0.0..=f32::INFINITY),
+                    );
+                    ui.end_row();
+                });
+
+                ui.horizontal(|ui| {
+                    let mut new_light = None;
+                    if ui.button("New Directional Light").clicked() {
+                        new_light = Some(Light {
+                            kind: LightKind::Directional,
+                            vector: Vector4 {
+                                x: 1.0,
+                                y: 0.0,
+                                z: 0.0,
+                                w: -1.0,
+                            },
+                            intensity: 1.0,
+                            ..Default::default()
+                        });
+                    }
+                    if ui.button("New Point Light").clicked() {
+                        new_light = Some(Light::default());
+                    }
+                    if let Some(light) = new_light {
+                        let edit = ListEdit::Add {
+                            index: self.scene.lights.len(),
+                            item: light,
+                        };
+                        self.history.push(ctx, Edit::Light(edit));
+                        self.scene.lights.push(light);
+                    }
+                });
+
+                let mut to_delete = vec![];
+                for (i, light) in self.scene.lights.iter_mut().enumerate() {
+                    let before = *light;
+                    ui.push_id(i, |ui| {
+                        egui::CollapsingHeader::new("Light").show(ui, |ui| {
+                            egui::Grid::new("Light Grid").show(ui, |ui| {
+                                ui.label("Kind:");
+                                ui.selectable_value(
+                                    &mut light.kind,
+                                    LightKind::Directional,
+                                    "Directional",
+                                );
+                                ui.selectable_value(&mut light.kind, LightKind::Point, "Point");
+                                ui.end_row();
+
+                                ui.label(match light.kind {
+                                    LightKind::Directional => "Direction:",
+                                    LightKind::Point => "Position:",
+                                });
+                                let vector = &mut light.vector;
+                                ui.add(egui::DragValue::new(&mut vector.x).prefix("x:").speed(0.1));
+                                ui.add(egui::DragValue::new(&mut vector.y).prefix("y:").speed(0.1));
+                                ui.add(egui::DragValue::new(&mut vector.z).prefix("z:").speed(0.1));
+                                ui.add(egui::DragValue::new(&mut vector.w).prefix("w:").speed(0.1));
+                                ui.end_row();
+
+                                ui.label("Color:");
+                                {
+                                    let mut color = [light.color.x, light.color.y, light.color.z];
+                                    ui.color_edit_button_rgb(&mut color);
+                                    let [x, y, z] = color;
+                                    light.color = Vector3 { x, y, z };
+                                }
+                                ui.end_row();
+
+                                ui.label("Intensity:");
+                                ui.add(egui::DragValue::new(&mut light.intensity).speed(0.1));
+                                light.intensity = light.intensity.max(0.0);
+                                ui.end_row();
+
+                                ui.label("Shadow Hardness:").on_hover_text(
+                                    "How sharp the edges of shadows are, lower is softer",
+                                );
+                                ui.add(egui::DragValue::new(&mut light.shadow_hardness).speed(0.1));
+                                light.shadow_hardness = light.shadow_hardness.max(0.1);
+                                ui.end_row();
+
+                                if ui.button("Delete").clicked() {
+                                    to_delete.push(i);
+                                }
+                            });
+                        });
+                    });
+                    if *light != before {
+                        let edit = ListEdit::Change {
+                            index: i,
+                            before,
+                            after: *light,
+                        };
+                        self.history.push(ctx, Edit::Light(edit));
+                    }
+                }
+                for i in to_delete.into_iter().rev() {
+                    let item = self.scene.lights.remove(i);
+                    self.history
+                        .push(ctx, Edit::Light(ListEdit::Remove { index: i, item }));
                 }
             });
 
@@ -476,21 +1659,22 @@ impl eframe::App for App {
             .resizable(false)
             .show(ctx, |ui| {
                 if ui.button("New Sphere").clicked() {
-                    self.spheres.push(Sphere {
-                        position: Vector4 {
-                            x: 8.0,
-                            y: 0.0,
-                            z: 0.0,
-                            w: 6.0,
-                        },
-                        rotation: Rotor::identity(),
-                    });
+                    let edit = ListEdit::Add {
+                        index: self.scene.spheres.len(),
+                        item: Sphere::default(),
+                    };
+                    self.history.push(ctx, Edit::Sphere(edit));
+                    self.scene.spheres.push(Sphere::default());
                 }
 
+                let selected = self.selection.and_then(|pick| pick.object);
                 let mut to_delete = vec![];
-                for (i, sphere) in self.spheres.iter_mut().enumerate() {
+                for (i, sphere) in self.scene.spheres.iter_mut().enumerate() {
+                    let before = *sphere;
                     ui.push_id(i, |ui| {
-                        ui.collapsing("Sphere", |ui| {
+                        let selected = selected == Some(Object::Sphere(i));
+                        let header = object_header(ui, "Sphere", selected, self.reveal_selection);
+                        header.show(ui, |ui| {
                             egui::Grid::new("Sphere Grid").show(ui, |ui| {
                                 ui.label("Position:");
                                 editing_spheres |= ui
@@ -522,6 +1706,49 @@ impl eframe::App for App {
                                     )
                                     .dragged();
                                 ui.end_row();
+
+                                ui.label("Velocity:");
+                                ui.add(
+                                    egui::DragValue::new(&mut sphere.velocity.x)
+                                        .prefix("x:")
+                                        .speed(0.1),
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut sphere.velocity.y)
+                                        .prefix("y:")
+                                        .speed(0.1),
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut sphere.velocity.z)
+                                        .prefix("z:")
+                                        .speed(0.1),
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut sphere.velocity.w)
+                                        .prefix("w:")
+                                        .speed(0.1),
+                                );
+                                ui.end_row();
+
+                                ui.label("Radius:");
+                                ui.add(egui::DragValue::new(&mut sphere.radius).speed(0.01));
+                                sphere.radius = sphere.radius.max(0.01);
+                                ui.end_row();
+
+                                ui.label("Color:");
+                                {
+                                    let mut color =
+                                        [sphere.color.x, sphere.color.y, sphere.color.z];
+                                    ui.color_edit_button_rgb(&mut color);
+                                    let [x, y, z] = color;
+                                    sphere.color = Vector3 { x, y, z };
+                                }
+                                ui.end_row();
+
+                                ui.label("Pattern Scale:");
+                                ui.add(egui::DragValue::new(&mut sphere.pattern_scale).speed(0.5));
+                                sphere.pattern_scale = sphere.pattern_scale.max(0.0);
+                                ui.end_row();
                             });
 
                             ui.collapsing("Orientation", |ui| {
@@ -529,6 +1756,71 @@ impl eframe::App for App {
                                     sphere.rotation = Rotor::identity();
                                 }
 
+                                ui.label("Plane Angles:").on_hover_text(
+                                    "The sphere is rotated in each plane in turn, in the frame left by the planes above it",
+                                );
+                                egui::Grid::new("Plane Angles").show(ui, |ui| {
+                                    let angles = sphere.rotation.plane_angles();
+                                    for (plane, name) in PLANE_NAMES.into_iter().enumerate() {
+                                        ui.label(format!("{name}:"));
+                                        let mut angle = angles[plane];
+                                        let response = ui.drag_angle(&mut angle);
+                                        editing_spheres |= response.dragged();
+                                        if response.changed() {
+                                            let mut angles = angles;
+                                            angles[plane] = angle;
+                                            sphere.rotation = Rotor::from_plane_angles(angles);
+                                        }
+
+                                        for (label, step) in
+                                            [("-", -ROTATION_STEP), ("+", ROTATION_STEP)]
+                                        {
+                                            if ui
+                                                .button(label)
+                                                .on_hover_text(format!(
+                                                    "Rotate by {}° in the sphere's own {name} plane",
+                                                    step.to_degrees()
+                                                ))
+                                                .clicked()
+                                            {
+                                                let mut angles = [0.0; 6];
+                                                angles[plane] = step;
+                                                sphere.rotation = sphere
+                                                    .rotation
+                                                    .then(Rotor::from_plane_angles(angles));
+                                            }
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+
+                                ui.horizontal(|ui| {
+                                    // in degrees, kept between frames until it is applied
+                                    let id = ui.id().with("Apply Rotation");
+                                    let mut degrees: [f32; 6] =
+                                        ui.data_mut(|data| data.get_temp(id)).unwrap_or_default();
+                                    for (angle, name) in degrees.iter_mut().zip(PLANE_NAMES) {
+                                        ui.add(
+                                            egui::DragValue::new(angle)
+                                                .prefix(format!("{name}:"))
+                                                .suffix("°"),
+                                        );
+                                    }
+                                    if ui
+                                        .button("Apply Rotation")
+                                        .on_hover_text(
+                                            "Rotate the sphere by these angles, as plane angles in its own frame",
+                                        )
+                                        .clicked()
+                                    {
+                                        let angles = degrees.map(f32::to_radians);
+                                        sphere.rotation =
+                                            sphere.rotation.then(Rotor::from_plane_angles(angles));
+                                        degrees = [0.0; 6];
+                                    }
+                                    ui.data_mut(|data| data.insert_temp(id, degrees));
+                                });
+
                                 ui.add_enabled_ui(false, |ui| {
                                     egui::Grid::new("Orientation").show(ui, |ui| {
                                         {
@@ -588,17 +1880,75 @@ impl eframe::App for App {
                             }
                         });
                     });
+                    if *sphere != before {
+                        let edit = ListEdit::Change {
+                            index: i,
+                            before,
+                            after: *sphere,
+                        };
+                        self.history.push(ctx, Edit::Sphere(edit));
+                    }
                 }
                 for i in to_delete.into_iter().rev() {
-                    self.spheres.remove(i);
+                    let item = self.scene.spheres.remove(i);
+                    self.history
+                        .push(ctx, Edit::Sphere(ListEdit::Remove { index: i, item }));
+                    remove_from_selection(&mut self.selection, Object::Sphere(i));
                 }
             });
+        self.reveal_selection = false;
+
+        if let Some(pick) = self.selection {
+            let mut open = true;
+            egui::Window::new("Selection")
+                .resizable(false)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label(match pick.object {
+                        Some(Object::Sphere(i)) => format!("Sphere {i}"),
+                        Some(Object::Wormhole(i)) => format!("Wormhole {i}"),
+                        None => "Sheet".into(),
+                    });
+                    egui::Grid::new("Selection Grid").show(ui, |ui| {
+                        for (name, v) in [("Position:", pick.position), ("Normal:", pick.normal)] {
+                            ui.label(name);
+                            ui.label(format!("x: {:.3}", v.x));
+                            ui.label(format!("y: {:.3}", v.y));
+                            ui.label(format!("z: {:.3}", v.z));
+                            ui.label(format!("w: {:.3}", v.w));
+                            ui.end_row();
+                        }
+                    });
+                });
+            if !open {
+                self.selection = None;
+            }
+        }
 
         if !editing_spheres {
-            self.project_spheres();
+            self.simulation.update(&mut self.scene, dt.as_secs_f32());
+            self.scene.project_spheres();
         }
 
-        self.camera.update(ctx, dt.as_secs_f32());
+        {
+            let sheets = &self.scene.sheets;
+            let wormholes = self.scene.gpu_wormholes();
+            let scene_sdf = (!sheets.is_empty()).then_some(|p| wormhole_sdf(sheets, &wormholes, p));
+            self.scene.camera.update(ctx, dt.as_secs_f32(), scene_sdf);
+        }
+
+        // the camera path takes over from the controls while it is playing
+        if let Some(frame) = self.export_frame {
+            self.timeline.time = frame as f32 / self.export_frame_rate;
+        } else {
+            let duration = animation::duration(&self.scene.keyframes);
+            self.timeline.update(dt.as_secs_f32(), duration);
+        }
+        if (self.timeline.playing || self.export_frame.is_some())
+            && let Some(keyframe) = animation::sample(&self.scene.keyframes, self.timeline.time)
+        {
+            keyframe.apply(&mut self.scene.camera);
+        }
 
         egui::CentralPanel::default()
             .frame(egui::Frame::NONE)
@@ -607,24 +1957,38 @@ impl eframe::App for App {
 
                 let width = response.rect.width() as u32;
                 let height = response.rect.height() as u32;
-                if width > 0
+                let (texture_width, texture_height) = self.render_scale.resolution(
+                    width,
+                    height,
+                    render_scale::max_pixels(&device.limits()),
+                );
+                let resized = width > 0
                     && height > 0
-                    && width != self.output_texture_width
-                    && height != self.output_texture_height
-                {
-                    self.output_texture_width = width;
-                    self.output_texture_height = height;
-                    (self.output_texture, self.output_texture_bind_group) =
-                        output_texture_and_bind_group(
-                            device,
-                            &self.output_texture_bind_group_layout,
-                            self.output_texture_width,
-                            self.output_texture_height,
-                        );
+                    && (texture_width != self.output_texture_width
+                        || texture_height != self.output_texture_height);
+                if resized {
+                    self.output_texture_width = texture_width;
+                    self.output_texture_height = texture_height;
+                    (
+                        self.output_texture,
+                        self.output_texture_view,
+                        self.output_texture_bind_group,
+                        self.tonemap_bind_group,
+                    ) = output_texture_and_bind_groups(
+                        device,
+                        &self.output_texture_bind_group_layout,
+                        &self.tonemap_bind_group_layout,
+                        &self.tonemapping_buffer,
+                        self.output_texture_width,
+                        self.output_texture_height,
+                    );
+                }
+                if resized || self.render_scale.filter != self.output_texture_filter {
+                    self.output_texture_filter = self.render_scale.filter;
                     renderer.write().update_egui_texture_from_wgpu_texture(
                         device,
-                        &self.output_texture,
-                        wgpu::FilterMode::Nearest,
+                        &self.output_texture_view,
+                        self.output_texture_filter.to_wgpu(),
                         self.output_texture_id,
                     );
                 }
@@ -635,6 +1999,74 @@ impl eframe::App for App {
                     egui::Rect::from_min_max(egui::pos2(0.0, 1.0), egui::pos2(1.0, 0.0)),
                     egui::Color32::WHITE,
                 );
+
+                if response.clicked()
+                    && let Some(position) = response.interact_pointer_pos()
+                    && width > 0
+                    && height > 0
+                {
+                    let x = (position.x - response.rect.left()) as u32;
+                    let y = (response.rect.bottom() - position.y) as u32;
+                    self.selection = picking::pick(
+                        &self.scene,
+                        x.min(width - 1),
+                        y.min(height - 1),
+                        width,
+                        height,
+                    );
+                    self.reveal_selection = true;
+                }
+
+                if let Some(pick) = self.selection {
+                    draw_selection(ui.painter(), response.rect, &self.scene, pick);
+
+                    let camera = self.scene.gpu_camera();
+                    match pick.object {
+                        Some(Object::Sphere(i))
+                            if self.scene.camera.view_mode == ViewMode::Embedded =>
+                        {
+                            let sphere = &mut self.scene.spheres[i];
+                            let before = *sphere;
+                            if gizmo::sphere(ui, response.rect, &camera, sphere) {
+                                // so that it isn't drawn off the surface for a frame
+                                self.scene.project_spheres();
+                                let edit = ListEdit::Change {
+                                    index: i,
+                                    before,
+                                    after: self.scene.spheres[i],
+                                };
+                                self.history.push(ctx, Edit::Sphere(edit));
+                            }
+                        }
+                        Some(Object::Wormhole(i))
+                            if self.scene.camera.view_mode == ViewMode::Embedded =>
+                        {
+                            let wormhole = &mut self.scene.wormholes[i];
+                            let before = *wormhole;
+                            if let Some(&w) = self.scene.sheets.get(wormhole.sheet)
+                                && gizmo::wormhole(ui, response.rect, &camera, wormhole, w)
+                            {
+                                let edit = ListEdit::Change {
+                                    index: i,
+                                    before,
+                                    after: *wormhole,
+                                };
+                                self.history.push(ctx, Edit::Wormhole(edit));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some(sheet) = camera_sheet(&self.scene) {
+                    ui.painter().text(
+                        response.rect.left_bottom() + egui::vec2(8.0, -8.0),
+                        egui::Align2::LEFT_BOTTOM,
+                        sheet,
+                        egui::FontId::proportional(16.0),
+                        egui::Color32::WHITE,
+                    );
+                }
             });
 
         {
@@ -642,7 +2074,20 @@ impl eframe::App for App {
             queue.write_buffer(
                 &self.camera_buffer,
                 0,
-                bytemuck::bytes_of(&self.camera.to_gpu()),
+                bytemuck::bytes_of(&self.scene.gpu_camera()),
+            );
+
+            let frame = self.path_tracer.next_frame(
+                &self.scene,
+                self.output_texture_width,
+                self.output_texture_height,
+            );
+            queue.write_buffer(&self.frame_buffer, 0, bytemuck::bytes_of(&frame));
+
+            queue.write_buffer(
+                &self.tonemapping_buffer,
+                0,
+                bytemuck::bytes_of(&self.scene.tonemapping.to_gpu()),
             );
 
             let mut objects_resized = false;
@@ -650,43 +2095,39 @@ impl eframe::App for App {
             queue.write_buffer(
                 &self.objects_info_buffer,
                 0,
-                bytemuck::bytes_of(&ObjectsInfo {
-                    wormholes_count: self.wormholes.len() as _,
-                    spheres_count: self.spheres.len() as _,
-                }),
+                bytemuck::bytes_of(&self.scene.objects_info()),
             );
 
-            if self.wormholes.len() * size_of::<Wormhole>() > self.wormholes_buffer.size() as _ {
-                self.wormholes_buffer = wormholes_buffer(device, self.wormholes.len());
+            if self.scene.sheets.len() * size_of::<f32>() > self.sheets_buffer.size() as _ {
+                self.sheets_buffer = sheets_buffer(device, self.scene.sheets.len());
                 objects_resized = true;
             }
             queue.write_buffer(
-                &self.wormholes_buffer,
+                &self.sheets_buffer,
                 0,
-                bytemuck::cast_slice(&self.wormholes),
+                bytemuck::cast_slice(&self.scene.sheets),
             );
 
-            if self.spheres.len() * size_of::<GpuSphere>() > self.spheres_buffer.size() as _ {
-                self.spheres_buffer = spheres_buffer(device, self.spheres.len());
+            let wormholes = self.scene.gpu_wormholes();
+            if wormholes.len() * size_of::<GpuWormhole>() > self.wormholes_buffer.size() as _ {
+                self.wormholes_buffer = wormholes_buffer(device, wormholes.len());
                 objects_resized = true;
             }
-            queue.write_buffer(
-                &self.spheres_buffer,
-                0,
-                bytemuck::cast_slice(
-                    &self
-                        .spheres
-                        .iter()
-                        .map(|sphere| GpuSphere {
-                            position: sphere.position,
-                            forward: sphere.rotation.x(),
-                            up: sphere.rotation.y(),
-                            right: sphere.rotation.z(),
-                            ana: sphere.rotation.w(),
-                        })
-                        .collect::<Vec<_>>(),
-                ),
-            );
+            queue.write_buffer(&self.wormholes_buffer, 0, bytemuck::cast_slice(&wormholes));
+
+            let spheres = self.scene.gpu_spheres();
+            if spheres.len() * size_of::<GpuSphere>() > self.spheres_buffer.size() as _ {
+                self.spheres_buffer = spheres_buffer(device, spheres.len());
+                objects_resized = true;
+            }
+            queue.write_buffer(&self.spheres_buffer, 0, bytemuck::cast_slice(&spheres));
+
+            let lights = self.scene.gpu_lights();
+            if lights.len() * size_of::<GpuLight>() > self.lights_buffer.size() as _ {
+                self.lights_buffer = lights_buffer(device, lights.len());
+                objects_resized = true;
+            }
+            queue.write_buffer(&self.lights_buffer, 0, bytemuck::cast_slice(&lights));
 
             if objects_resized {
                 self.objects_bind_group = objects_bind_group(
@@ -695,6 +2136,8 @@ impl eframe::App for App {
                     &self.objects_info_buffer,
                     &self.wormholes_buffer,
                     &self.spheres_buffer,
+                    &self.sheets_buffer,
+                    &self.lights_buffer,
                 );
             }
         }
@@ -703,27 +2146,76 @@ impl eframe::App for App {
             let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Command Encoder"),
             });
-            {
-                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                    label: Some("Compute Pass"),
-                    timestamp_writes: None,
-                });
+            self.encode_passes(
+                &mut encoder,
+                &self.output_texture_bind_group,
+                &self.tonemap_bind_group,
+                self.output_texture_width,
+                self.output_texture_height,
+            );
+            queue.submit(core::iter::once(encoder.finish()));
+        }
 
-                compute_pass.set_pipeline(&self.ray_tracing_pipeline);
-                compute_pass.set_bind_group(0, &self.output_texture_bind_group, &[]);
-                compute_pass.set_bind_group(1, &self.camera_bind_group, &[]);
-                compute_pass.set_bind_group(2, &self.objects_bind_group, &[]);
-                compute_pass.dispatch_workgroups(
-                    self.output_texture_width.div_ceil(16),
-                    self.output_texture_height.div_ceil(16),
-                    1,
-                );
+        let image = if std::mem::take(&mut self.save_screenshot) {
+            Some(screenshot::read_texture(
+                device,
+                queue,
+                &self.output_texture,
+            ))
+        } else if std::mem::take(&mut self.save_high_resolution) {
+            Some(self.render_tiled(
+                device,
+                queue,
+                self.high_resolution_width,
+                self.high_resolution_height,
+            ))
+        } else {
+            None
+        };
+        if let Some(frame) = self.export_frame {
+            let path = screenshot::numbered_path(&self.screenshot_path, frame);
+            let duration = animation::duration(&self.scene.keyframes);
+            let saved = screenshot::read_texture(device, queue, &self.output_texture)
+                .map_err(|error| format!("Failed to export frame {frame}: {error}"))
+                .and_then(|image| {
+                    screenshot::save(&image, &self.scene, &path)
+                        .map_err(|error| format!("Failed to save {}: {error}", path.display()))
+                });
+            match saved {
+                Ok(()) if (frame + 1) as f32 / self.export_frame_rate <= duration => {
+                    self.export_frame = Some(frame + 1);
+                }
+                Ok(()) => {
+                    self.export_frame = None;
+                    self.screenshot_status = Some(format!("Exported {} frames", frame + 1));
+                }
+                Err(status) => {
+                    self.export_frame = None;
+                    self.screenshot_status = Some(status);
+                }
             }
-            queue.submit(core::iter::once(encoder.finish()));
+        }
+
+        if let Some(image) = image {
+            self.screenshot_status = Some(
+                match image
+                    .map(|image| screenshot::save(&image, &self.scene, &self.screenshot_path))
+                {
+                    Ok(Ok(())) => format!("Saved {}", self.screenshot_path),
+                    Ok(Err(error)) => format!("Failed to save {}: {error}", self.screenshot_path),
+                    Err(error) => format!("Failed to take the screenshot: {error}"),
+                },
+            );
         }
 
         ctx.request_repaint();
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(SCENE_KEY, self.scene.to_json());
+        storage.set_string(SCENE_PATH_KEY, self.scene_path.clone());
+        storage.set_string(SCREENSHOT_PATH_KEY, self.screenshot_path.clone());
+    }
 }
 
 fn main() -> eframe::Result<()> {
@@ -737,7 +2229,6 @@ fn main() -> eframe::Result<()> {
                     instance_descriptor: wgpu::InstanceDescriptor::from_env_or_default(),
                     device_descriptor: Arc::new(|adapter| wgpu::DeviceDescriptor {
                         label: Some("Wgpu Device"),
-                        required_features: wgpu::Features::BGRA8UNORM_STORAGE,
                         required_limits: adapter.limits(),
                         ..Default::default()
                     }),
@@ -0,0 +1,278 @@
+use crate::sdf;
+use bytemuck::NoUninit;
+use math::{Rotor, Vector2, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, NoUninit)]
+#[repr(C)]
+pub struct ObjectsInfo {
+    pub wormholes_count: u32,
+    pub spheres_count: u32,
+    pub sheets_count: u32,
+    pub lights_count: u32,
+    pub ambient: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Wormhole {
+    /// Index of the sheet that `position` is on, the wormhole connects it to the sheet above
+    pub sheet: usize,
+    /// Position of the mouth on the lower sheet
+    pub position: Vector3<f32>,
+    /// Position of the mouth on the upper sheet, if it isn't directly across from `position`
+    pub other_position: Option<Vector3<f32>>,
+    pub throat_size: f32,
+    /// Radius of the curve where the throat meets each sheet, overriding the scene's throat length.
+    /// Anything shorter than half the distance between the sheets leaves a straight tube between the two curves
+    pub throat_length: Option<f32>,
+}
+
+impl Default for Wormhole {
+    fn default() -> Self {
+        Self {
+            sheet: 0,
+            position: Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            other_position: None,
+            throat_size: 3.0,
+            throat_length: None,
+        }
+    }
+}
+
+impl Wormhole {
+    pub fn other_position(&self) -> Vector3<f32> {
+        self.other_position.unwrap_or(self.position)
+    }
+
+    /// Half the distance between the two sheets this wormhole connects,
+    /// or `None` if they don't both exist or aren't in increasing order
+    pub fn sheet_distance(&self, sheets: &[f32]) -> Option<f32> {
+        let lower = sheets.get(self.sheet)?;
+        let upper = sheets.get(self.sheet + 1)?;
+        Some((upper - lower) * 0.5).filter(|&distance| distance > 0.0)
+    }
+
+    /// The radius of the curves at each mouth, which can't be longer than the distance to the sheets
+    pub fn throat_length(&self, scene_throat_length: f32, sheet_distance: f32) -> f32 {
+        self.throat_length
+            .unwrap_or(scene_throat_length)
+            .clamp(0.0, sheet_distance)
+    }
+
+    /// Returns `None` if the wormhole can't be drawn because its sheets are missing
+    pub fn to_gpu(&self, sheets: &[f32], scene_throat_length: f32) -> Option<GpuWormhole> {
+        let sheet_distance = self.sheet_distance(sheets)?;
+        Some(GpuWormhole {
+            position: self.position,
+            throat_size: self.throat_size,
+            other_position: self.other_position(),
+            throat_length: self.throat_length(scene_throat_length, sheet_distance),
+            sheet: self.sheet as _,
+            _padding: [0; 3],
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, NoUninit)]
+#[repr(C)]
+pub struct GpuWormhole {
+    pub position: Vector3<f32>,
+    pub throat_size: f32,
+    pub other_position: Vector3<f32>,
+    pub throat_length: f32,
+    pub sheet: u32,
+    pub _padding: [u32; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sphere {
+    pub position: Vector4<f32>,
+    pub rotation: Rotor,
+    pub velocity: Vector4<f32>,
+    pub radius: f32,
+    pub color: Vector3<f32>,
+    pub pattern_scale: f32,
+}
+
+impl Default for Sphere {
+    fn default() -> Self {
+        Self {
+            position: Vector4 {
+                x: 8.0,
+                y: 0.0,
+                z: 0.0,
+                w: 6.0,
+            },
+            rotation: Rotor::identity(),
+            velocity: Vector4 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 0.0,
+            },
+            radius: 0.5,
+            color: Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            pattern_scale: 100.0,
+        }
+    }
+}
+
+impl Sphere {
+    /// `surface_position` is the closest point on the surface, which is where the sphere is seen in
+    /// [`ViewMode::Surface`](crate::camera::ViewMode::Surface)
+    pub fn to_gpu(&self, surface_position: Vector4<f32>) -> GpuSphere {
+        GpuSphere {
+            position: self.position,
+            forward: self.rotation.x(),
+            up: self.rotation.y(),
+            right: self.rotation.z(),
+            ana: self.rotation.w(),
+            surface_position,
+            color: self.color,
+            radius: self.radius,
+            pattern_scale: self.pattern_scale,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, NoUninit)]
+#[repr(C)]
+pub struct GpuSphere {
+    pub position: Vector4<f32>,
+    pub forward: Vector4<f32>,
+    pub up: Vector4<f32>,
+    pub right: Vector4<f32>,
+    pub ana: Vector4<f32>,
+    pub surface_position: Vector4<f32>,
+    pub color: Vector3<f32>,
+    pub radius: f32,
+    pub pattern_scale: f32,
+    pub _padding: [f32; 3],
+}
+
+pub(crate) fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Signed distance to the sheets and the wormholes between them, which is negative in every other
+/// space between the sheets. Must be kept in sync with `scene_sdf` in `ray_tracing.slang`
+pub fn wormhole_sdf(sheets: &[f32], wormholes: &[GpuWormhole], p: Vector4<f32>) -> f32 {
+    let position = Vector3 {
+        x: p.x,
+        y: p.y,
+        z: p.z,
+    };
+
+    let mut distance = f32::MAX;
+
+    // each sheet, with a hole cut out for each mouth on it
+    for (i, &sheet) in sheets.iter().enumerate() {
+        let mut hole = 0.0f32;
+        for wormhole in wormholes {
+            let mouth = if wormhole.sheet as usize == i {
+                wormhole.position
+            } else if wormhole.sheet as usize + 1 == i {
+                wormhole.other_position
+            } else {
+                continue;
+            };
+            let radius = wormhole.throat_size + wormhole.throat_length;
+            hole = hole.max(radius - (position - mouth).magnitude());
+        }
+        distance = distance.min(
+            Vector2 {
+                x: p.w - sheet,
+                y: hole,
+            }
+            .magnitude(),
+        );
+    }
+
+    // how many sheets are below `p`, the surface is crossed at each one
+    let space = sheets.iter().filter(|&&sheet| sheet <= p.w).count();
+    let mut in_throat = false;
+
+    for wormhole in wormholes {
+        let lower = sheets[wormhole.sheet as usize];
+        let upper = sheets[wormhole.sheet as usize + 1];
+        let sheet_distance = (upper - lower) * 0.5;
+        let offset = wormhole.other_position - wormhole.position;
+
+        // bend the throat between the two mouths
+        let centre = wormhole.position + offset * smoothstep(lower, upper, p.w);
+        let radial = (position - centre).magnitude();
+        let height = f32::abs(p.w - (lower + upper) * 0.5);
+
+        // relative to the centre of the curve at the nearest mouth, the curve is the quarter circle
+        // facing the throat and the sheet, and the straight part of the throat runs down from it
+        let q = Vector2 {
+            x: wormhole.throat_size + wormhole.throat_length - radial,
+            y: height - (sheet_distance - wormhole.throat_length),
+        };
+        let tube = Vector2 {
+            x: radial - wormhole.throat_size,
+            y: q.y.max(0.0),
+        }
+        .magnitude();
+        let curve = if q.x >= 0.0 && q.y >= 0.0 {
+            f32::abs(q.magnitude() - wormhole.throat_length)
+        } else {
+            // closest to the edge of the mouth
+            Vector2 {
+                x: q.x,
+                y: q.y - wormhole.throat_length,
+            }
+            .magnitude()
+        };
+
+        // bending the throat stretches distances by up to the steepest slope of the smoothstep
+        let slope = 1.5 * offset.magnitude() / (upper - lower);
+        distance = distance.min(f32::min(tube, curve) / f32::sqrt(1.0 + slope * slope));
+
+        // the inside of the throat is on the other side of the surface to the rest of its space,
+        // which is the space between the sheets inset by the throat length and rounded back out
+        if wormhole.sheet as usize + 1 == space {
+            let d = q.map(|c| c.max(0.0)).magnitude() + f32::min(f32::max(q.x, q.y), 0.0)
+                - wormhole.throat_length;
+            in_throat |= d > 0.0;
+        }
+    }
+
+    if (space % 2 == 1) != in_throat {
+        -distance
+    } else {
+        distance
+    }
+}
+
+/// Moves each sphere so that it rests on the surface given by `scene_sdf`, on whichever side of the
+/// surface it is already on, and rotates it so that its ana axis lines up with the surface normal
+pub fn project_spheres(scene_sdf: impl Fn(Vector4<f32>) -> f32, spheres: &mut [Sphere]) {
+    let scene_sdf = &scene_sdf;
+    for sphere in spheres {
+        {
+            let distance = sphere.radius.copysign(scene_sdf(sphere.position));
+            sphere.position = sdf::project(scene_sdf, sphere.position, distance);
+        }
+
+        {
+            let normal = sdf::normal(scene_sdf, sphere.position);
+            if normal.square_magnitude() > 0.0 {
+                let old_normal = sphere.rotation.w();
+                let correction_rotation =
+                    Rotor::from_to_vector(old_normal, normal * old_normal.dot(normal).signum());
+                sphere.rotation = correction_rotation.then(sphere.rotation).normalised();
+            }
+        }
+    }
+}
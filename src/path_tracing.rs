@@ -0,0 +1,86 @@
+use crate::scene::Scene;
+use bytemuck::NoUninit;
+
+pub const DEFAULT_MAX_BOUNCES: u32 = 4;
+
+/// Progressive path tracing, which adds another frame of samples to each pixel until anything
+/// that is drawn changes
+///
+/// The frames are also what the camera accumulates when it isn't path tracing
+#[derive(Debug)]
+pub struct PathTracer {
+    pub enabled: bool,
+    /// How many times a ray can bounce after it first hits something
+    pub max_bounces: u32,
+    frame_index: u32,
+    /// Everything that was uploaded to the shader for the last frame, to tell when it changes
+    state: Vec<u8>,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bounces: DEFAULT_MAX_BOUNCES,
+            frame_index: 0,
+            state: vec![],
+        }
+    }
+}
+
+impl PathTracer {
+    /// The frame to draw a `width` by `height` view of `scene` for, which starts again from 0 when
+    /// the view would look any different
+    pub fn next_frame(&mut self, scene: &Scene, width: u32, height: u32) -> GpuFrame {
+        let mut state = vec![];
+        state.extend_from_slice(bytemuck::bytes_of(&scene.gpu_camera()));
+        state.extend_from_slice(bytemuck::bytes_of(&scene.objects_info()));
+        state.extend_from_slice(bytemuck::cast_slice(&scene.sheets));
+        state.extend_from_slice(bytemuck::cast_slice(&scene.gpu_wormholes()));
+        state.extend_from_slice(bytemuck::cast_slice(&scene.gpu_spheres()));
+        state.extend_from_slice(bytemuck::cast_slice(&scene.gpu_lights()));
+        state.extend_from_slice(bytemuck::cast_slice(&[
+            width,
+            height,
+            self.enabled as u32,
+            self.max_bounces,
+        ]));
+
+        if state == self.state {
+            self.frame_index += 1;
+        } else {
+            self.frame_index = 0;
+            self.state = state;
+        }
+
+        GpuFrame {
+            index: self.frame_index,
+            max_bounces: self.max_bounces,
+            offset: [0, 0],
+            size: [width, height],
+        }
+    }
+
+    /// How many frames have been averaged together, including the one that was last drawn
+    pub fn frames(&self) -> u32 {
+        self.frame_index + 1
+    }
+
+    /// Starts accumulating again from the next frame
+    pub fn restart(&mut self) {
+        self.state.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, NoUninit)]
+#[repr(C)]
+pub struct GpuFrame {
+    /// How many frames have been accumulated since anything changed
+    pub index: u32,
+    pub max_bounces: u32,
+    /// Where the texture that is drawn to is in the whole image, in pixels
+    pub offset: [u32; 2],
+    /// The size of the whole image, which is only bigger than the texture when it is drawn in
+    /// tiles
+    pub size: [u32; 2],
+}
@@ -0,0 +1,126 @@
+//! Spheres moving along the surface, stepped at a fixed rate so that the result doesn't depend on the frame rate.
+
+use crate::{
+    objects::{Sphere, project_spheres, wormhole_sdf},
+    scene::Scene,
+    sdf,
+};
+use math::Vector4;
+
+/// Length of a single step of the simulation in seconds
+pub const TIMESTEP: f32 = 1.0 / 120.0;
+/// Gravity used when it is turned on, which pulls down the throats of the wormholes
+pub const DEFAULT_GRAVITY: Vector4<f32> = Vector4 {
+    x: 0.0,
+    y: 0.0,
+    z: 0.0,
+    w: -9.8,
+};
+/// How many steps a single update can run, so that the simulation slows down instead of falling
+/// further and further behind when it can't keep up
+const MAX_STEPS_PER_UPDATE: u32 = 30;
+/// How much of the speed towards each other is kept when two spheres bounce off each other
+const RESTITUTION: f32 = 0.9;
+
+#[derive(Debug, Default)]
+pub struct Simulation {
+    pub playing: bool,
+    /// Time that has passed but hasn't been simulated yet, because it is shorter than a step
+    accumulator: f32,
+}
+
+impl Simulation {
+    /// Runs as many steps as fit in `dt` plus whatever was left over from the last update
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        if !self.playing {
+            self.accumulator = 0.0;
+            return;
+        }
+
+        self.accumulator = (self.accumulator + dt).min(TIMESTEP * MAX_STEPS_PER_UPDATE as f32);
+        while self.accumulator >= TIMESTEP {
+            step(scene);
+            self.accumulator -= TIMESTEP;
+        }
+    }
+}
+
+/// Advances the spheres in the scene by [`TIMESTEP`]
+pub fn step(scene: &mut Scene) {
+    if scene.sheets.is_empty() {
+        return;
+    }
+    let sheets = &scene.sheets;
+    let wormholes = scene.gpu_wormholes();
+    step_spheres(
+        |p| wormhole_sdf(sheets, &wormholes, p),
+        scene.gravity,
+        &mut scene.spheres,
+        TIMESTEP,
+    );
+}
+
+/// Moves each sphere along the surface given by `scene_sdf`, accelerated by the part of `gravity`
+/// along the surface, and bounces spheres that overlap off each other
+pub fn step_spheres(
+    scene_sdf: impl Fn(Vector4<f32>) -> f32,
+    gravity: Option<Vector4<f32>>,
+    spheres: &mut [Sphere],
+    dt: f32,
+) {
+    let scene_sdf = &scene_sdf;
+
+    for sphere in spheres.iter_mut() {
+        if let Some(gravity) = gravity {
+            let normal = sdf::normal(scene_sdf, sphere.position);
+            sphere.velocity += (gravity - normal * gravity.dot(normal)) * dt;
+        }
+        sphere.position += sphere.velocity * dt;
+    }
+
+    for i in 0..spheres.len() {
+        let (before, after) = spheres.split_at_mut(i + 1);
+        let a = &mut before[i];
+        for b in after {
+            collide(a, b);
+        }
+    }
+
+    project_spheres(scene_sdf, spheres);
+
+    // turn the velocity to follow the surface, without changing the speed
+    for sphere in spheres.iter_mut() {
+        let normal = sdf::normal(scene_sdf, sphere.position);
+        let tangent = sphere.velocity - normal * sphere.velocity.dot(normal);
+        sphere.velocity = if tangent.square_magnitude() > 0.0 {
+            tangent.normalised() * sphere.velocity.magnitude()
+        } else {
+            tangent
+        };
+    }
+}
+
+/// Pushes two spheres apart if they overlap, and if they are moving towards each other bounces them apart
+fn collide(a: &mut Sphere, b: &mut Sphere) {
+    let offset = b.position - a.position;
+    let distance = offset.magnitude();
+    let overlap = a.radius + b.radius - distance;
+    if overlap <= 0.0 || distance == 0.0 {
+        return;
+    }
+    let normal = offset / distance;
+
+    // the mass of each sphere is its 4d volume, up to a constant
+    let a_mass = a.radius.powi(4);
+    let b_mass = b.radius.powi(4);
+    let total_mass = a_mass + b_mass;
+    a.position -= normal * (overlap * b_mass / total_mass);
+    b.position += normal * (overlap * a_mass / total_mass);
+
+    let approach_speed = (a.velocity - b.velocity).dot(normal);
+    if approach_speed > 0.0 {
+        let impulse = (1.0 + RESTITUTION) * approach_speed / (1.0 / a_mass + 1.0 / b_mass);
+        a.velocity -= normal * (impulse / a_mass);
+        b.velocity += normal * (impulse / b_mass);
+    }
+}
@@ -0,0 +1,154 @@
+//! Finding what is under the mouse, by tracing the same ray as `trace_rays` on the CPU.
+
+use crate::{
+    camera::{GpuCamera, ViewMode},
+    cpu::{self, Bindings},
+    objects::smoothstep,
+    scene::Scene,
+    sdf,
+};
+use math::{Vector3, Vector4};
+
+/// An object in the scene, by its index in [`Scene::spheres`] or [`Scene::wormholes`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Object {
+    Sphere(usize),
+    Wormhole(usize),
+}
+
+impl Object {
+    /// What this object's index becomes once `removed` has been deleted, or `None` if this is `removed`
+    pub fn after_removing(self, removed: Object) -> Option<Object> {
+        let shift = |i: usize, removed: usize| match i.cmp(&removed) {
+            std::cmp::Ordering::Less => Some(i),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(i - 1),
+        };
+        match (self, removed) {
+            (Object::Sphere(i), Object::Sphere(removed)) => shift(i, removed).map(Object::Sphere),
+            (Object::Wormhole(i), Object::Wormhole(removed)) => {
+                shift(i, removed).map(Object::Wormhole)
+            }
+            _ => Some(self),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pick {
+    /// `None` when the ray hit a sheet away from any wormhole
+    pub object: Option<Object>,
+    pub position: Vector4<f32>,
+    /// Facing back along the ray
+    pub normal: Vector4<f32>,
+}
+
+/// Traces the ray through pixel `x`, `y` of a `width` by `height` view, with row 0 at the bottom
+///
+/// Only the embedded view can be picked from, rays in the surface view never leave the surface
+pub fn pick(scene: &Scene, x: u32, y: u32, width: u32, height: u32) -> Option<Pick> {
+    if scene.camera.view_mode != ViewMode::Embedded {
+        return None;
+    }
+
+    let bindings = Bindings::new(scene);
+    let ray = cpu::camera_ray(
+        &bindings.camera,
+        x as f32 + 0.5,
+        y as f32 + 0.5,
+        width,
+        height,
+    );
+
+    let mut nearest = None;
+    if let Some(hit) = cpu::hit_scene(&bindings, ray) {
+        let position = ray.origin + ray.direction * hit.distance;
+        let mut normal = sdf::normal(|p| bindings.scene_sdf(p), position);
+        if normal.dot(ray.direction) > 0.0 {
+            normal = -normal;
+        }
+        nearest = Some((
+            hit.distance,
+            Pick {
+                object: wormhole_at(scene, position).map(Object::Wormhole),
+                position,
+                normal,
+            },
+        ));
+    }
+
+    for (i, sphere) in bindings.spheres.iter().enumerate() {
+        if let Some(hit) = cpu::hit_sphere(sphere, ray)
+            && nearest.is_none_or(|(distance, _)| hit.distance < distance)
+        {
+            let position = ray.origin + ray.direction * hit.distance;
+            nearest = Some((
+                hit.distance,
+                Pick {
+                    object: Some(Object::Sphere(i)),
+                    position,
+                    normal: (position - sphere.position) / sphere.radius,
+                },
+            ));
+        }
+    }
+
+    nearest.map(|(_, pick)| pick)
+}
+
+/// The wormhole whose throat, or the curve around either of its mouths, `p` on the surface is part of
+fn wormhole_at(scene: &Scene, p: Vector4<f32>) -> Option<usize> {
+    // how far off the surface a point can be and still be on it
+    const TOLERANCE: f32 = 0.01;
+
+    let position = Vector3 {
+        x: p.x,
+        y: p.y,
+        z: p.z,
+    };
+
+    scene
+        .wormholes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, wormhole)| {
+            let wormhole = wormhole.to_gpu(&scene.sheets, scene.throat_length)?;
+            let lower = scene.sheets[wormhole.sheet as usize];
+            let upper = scene.sheets[wormhole.sheet as usize + 1];
+            if p.w < lower - TOLERANCE || p.w > upper + TOLERANCE {
+                return None;
+            }
+
+            // the same bend as in `wormhole_sdf`
+            let offset = wormhole.other_position - wormhole.position;
+            let centre = wormhole.position + offset * smoothstep(lower, upper, p.w);
+            let radial = (position - centre).magnitude();
+            (radial <= wormhole.throat_size + wormhole.throat_length + TOLERANCE)
+                .then_some((i, radial))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| i)
+}
+
+/// Where `p` appears in a `width` by `height` view, as the inverse of `camera_ray` with row 0 at
+/// the bottom, ignoring how far `p` is off the view in the ana direction
+///
+/// `None` if `p` is behind the camera
+pub fn screen_position(
+    camera: &GpuCamera,
+    p: Vector4<f32>,
+    width: f32,
+    height: f32,
+) -> Option<[f32; 2]> {
+    let relative = p - camera.position;
+    let depth = relative.dot(camera.forward);
+    if depth <= 0.0 {
+        return None;
+    }
+
+    let aspect = width / height;
+    let fov_scale = f32::tan(camera.fov * 0.5);
+    let u = relative.dot(camera.right) / (depth * fov_scale * aspect);
+    let v = relative.dot(camera.up) / (depth * fov_scale);
+    Some([(u + 1.0) * 0.5 * width, (v + 1.0) * 0.5 * height])
+}
@@ -0,0 +1,116 @@
+use eframe::wgpu;
+
+pub const MIN_SCALE: f32 = 0.25;
+pub const MAX_SCALE: f32 = 2.0;
+
+/// How often the automatic scale is adjusted, in seconds, so that it changes on the average frame
+/// rate rather than every slow frame
+const ADJUST_INTERVAL: f32 = 0.5;
+
+/// How the rendered image is sampled when it is stretched over the view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// Blocky, but shows exactly what was rendered
+    #[default]
+    Nearest,
+    Bilinear,
+}
+
+impl Filter {
+    pub const ALL: [Self; 2] = [Self::Nearest, Self::Bilinear];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nearest => "Nearest",
+            Self::Bilinear => "Bilinear",
+        }
+    }
+
+    pub fn to_wgpu(self) -> wgpu::FilterMode {
+        match self {
+            Self::Nearest => wgpu::FilterMode::Nearest,
+            Self::Bilinear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+/// The resolution that the view is rendered at, relative to its size on the screen
+#[derive(Debug)]
+pub struct RenderScale {
+    /// Between [`MIN_SCALE`] and [`MAX_SCALE`], where 1 is a pixel for each point of the view
+    pub scale: f32,
+    /// Whether the scale is adjusted to keep the frame rate at `target_fps`
+    pub automatic: bool,
+    pub target_fps: f32,
+    pub filter: Filter,
+    /// The time and number of frames since the automatic scale was last adjusted
+    elapsed: f32,
+    frames: u32,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            automatic: false,
+            target_fps: 60.0,
+            filter: Filter::default(),
+            elapsed: 0.0,
+            frames: 0,
+        }
+    }
+}
+
+/// The most pixels that can be rendered at once, as each one has an `[f32; 4]` in the accumulation
+/// buffer, which has to fit in a single storage buffer binding
+pub fn max_pixels(limits: &wgpu::Limits) -> u64 {
+    let max_size = limits
+        .max_buffer_size
+        .min(limits.max_storage_buffer_binding_size as u64);
+    max_size / size_of::<[f32; 4]>() as u64
+}
+
+impl RenderScale {
+    /// The size to render a `width` by `height` view at, which is never empty, shrunk to keep the
+    /// aspect ratio if it would be more than `max_pixels`
+    pub fn resolution(&self, width: u32, height: u32, max_pixels: u64) -> (u32, u32) {
+        let scale = |size: u32| ((size as f32 * self.scale).round() as u32).max(1);
+        let (width, height) = (scale(width), scale(height));
+
+        let pixels = width as u64 * height as u64;
+        if pixels <= max_pixels {
+            return (width, height);
+        }
+        let shrink = f64::sqrt(max_pixels as f64 / pixels as f64);
+        let shrink = |size: u32| ((size as f64 * shrink) as u32).max(1);
+        (shrink(width), shrink(height))
+    }
+
+    /// Counts a frame that took `dt` seconds, and when automatic, moves the scale towards the one
+    /// that would reach the target frame rate
+    pub fn update(&mut self, dt: f32) {
+        if !self.automatic {
+            self.elapsed = 0.0;
+            self.frames = 0;
+            return;
+        }
+
+        self.elapsed += dt;
+        self.frames += 1;
+        if self.elapsed < ADJUST_INTERVAL {
+            return;
+        }
+
+        let fps = self.frames as f32 / self.elapsed;
+        self.elapsed = 0.0;
+        self.frames = 0;
+
+        // the time a frame takes goes roughly with the number of pixels, which is the square of
+        // the scale, and anything within 10% is left alone so that it doesn't keep changing
+        let ratio = fps / self.target_fps.max(1.0);
+        if !(0.9..=1.1).contains(&ratio) {
+            self.scale *= ratio.sqrt().clamp(0.8, 1.1);
+        }
+        self.scale = self.scale.clamp(MIN_SCALE, MAX_SCALE);
+    }
+}
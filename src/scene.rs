@@ -0,0 +1,352 @@
+use crate::{
+    animation::Keyframe,
+    camera::{Bookmark, Camera, GpuCamera, ViewMode},
+    lights::{GpuLight, Light},
+    objects::{
+        GpuSphere, GpuWormhole, ObjectsInfo, Sphere, Wormhole, project_spheres, wormhole_sdf,
+    },
+    sdf,
+    tonemapping::Tonemapping,
+};
+use math::Vector4;
+use serde::{Deserialize, Serialize};
+use std::{fmt, io, path::Path};
+
+/// Everything needed to reproduce what is on screen, in the format of a scene file
+#[derive(Serialize, Deserialize)]
+pub struct Scene {
+    pub camera: Camera,
+    /// The w of each sheet, in increasing order
+    pub sheets: Vec<f32>,
+    /// Radius of the curve where each wormhole's throat meets its sheets, unless the wormhole overrides it
+    pub throat_length: f32,
+    pub wormholes: Vec<Wormhole>,
+    pub spheres: Vec<Sphere>,
+    /// Acceleration of the spheres, only the part along the surface has any effect
+    pub gravity: Option<Vector4<f32>>,
+    pub bookmarks: Vec<Bookmark>,
+    /// The camera path, in the order they were added rather than in order of time
+    pub keyframes: Vec<Keyframe>,
+    pub lights: Vec<Light>,
+    /// Light that reaches everywhere, even in shadow
+    pub ambient: f32,
+    pub tonemapping: Tonemapping,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            camera: Camera::default(),
+            sheets: vec![-4.0, 4.0],
+            throat_length: 4.0,
+            wormholes: vec![Wormhole::default()],
+            spheres: vec![Sphere::default()],
+            gravity: None,
+            bookmarks: vec![],
+            keyframes: vec![],
+            lights: vec![Light::default()],
+            ambient: 0.15,
+            tonemapping: Tonemapping::default(),
+        }
+    }
+}
+
+/// The version written to new scene files, bump this and add a step to [`migrate`] when the format changes
+pub const SCENE_VERSION: u32 = 13;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    MissingVersion,
+    UnsupportedVersion(u64),
+    /// The sheets aren't in increasing order, which would turn the space between them inside out
+    UnsortedSheets,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "{error}"),
+            SceneError::Json(error) => write!(f, "invalid scene file: {error}"),
+            SceneError::MissingVersion => write!(f, "the scene file has no version"),
+            SceneError::UnsupportedVersion(version) => write!(
+                f,
+                "the scene file is version {version}, but only up to version {SCENE_VERSION} is supported"
+            ),
+            SceneError::UnsortedSheets => write!(f, "the sheets aren't in increasing order of w"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+#[derive(Serialize)]
+struct VersionedScene<'a> {
+    version: u32,
+    #[serde(flatten)]
+    scene: &'a Scene,
+}
+
+/// Upgrades a scene file from `version` to [`SCENE_VERSION`]
+///
+/// Each change to the format adds a step here that rewrites the json when `version` is older than the change
+fn migrate(scene: &mut serde_json::Value, version: u32) {
+    if version < 2 {
+        // spheres gained a radius, color, and pattern scale, which used to be hard-coded
+        let default = serde_json::to_value(Sphere::default()).unwrap();
+        for sphere in objects_mut(scene, "spheres") {
+            for field in ["radius", "color", "pattern_scale"] {
+                sphere.insert(field.into(), default[field].clone());
+            }
+        }
+    }
+    if version < 3 {
+        // the sheet separation and throat length used to be hard-coded
+        if let Some(scene) = scene.as_object_mut() {
+            scene.insert("sheet_separation".into(), 8.0.into());
+            scene.insert("throat_length".into(), 4.0.into());
+        }
+    }
+    if version < 4 {
+        // there used to be exactly two sheets, either side of `w = 0`
+        for wormhole in objects_mut(scene, "wormholes") {
+            wormhole.insert("sheet".into(), 0.into());
+        }
+        if let Some(scene) = scene.as_object_mut() {
+            let sheet_distance = scene
+                .remove("sheet_separation")
+                .and_then(|separation| separation.as_f64())
+                .unwrap_or(8.0)
+                * 0.5;
+            scene.insert(
+                "sheets".into(),
+                vec![-sheet_distance, sheet_distance].into(),
+            );
+        }
+    }
+    if version < 5 {
+        // the surface view was added
+        if let Some(camera) = scene
+            .get_mut("camera")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            camera.insert(
+                "view_mode".into(),
+                serde_json::to_value(ViewMode::Embedded).unwrap(),
+            );
+        }
+    }
+    if version < 6 {
+        // walking on the surface was added
+        let default = serde_json::to_value(Camera::default()).unwrap();
+        if let Some(camera) = scene
+            .get_mut("camera")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            for field in ["walk_rotation", "movement", "eye_height"] {
+                camera.insert(field.into(), default[field].clone());
+            }
+        }
+    }
+    if version < 7 {
+        // collision with the surface was added
+        let default = serde_json::to_value(Camera::default()).unwrap();
+        if let Some(camera) = scene
+            .get_mut("camera")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            for field in ["collision", "min_distance"] {
+                camera.insert(field.into(), default[field].clone());
+            }
+        }
+    }
+    if version < 8 {
+        // spheres started moving
+        let default = serde_json::to_value(Sphere::default()).unwrap();
+        for sphere in objects_mut(scene, "spheres") {
+            sphere.insert("velocity".into(), default["velocity"].clone());
+        }
+    }
+    if version < 9 {
+        // camera bookmarks were added
+        if let Some(scene) = scene.as_object_mut() {
+            scene.insert("bookmarks".into(), Vec::<serde_json::Value>::new().into());
+        }
+    }
+    if version < 10 {
+        // lighting was added, which used to be the same everywhere
+        let default = serde_json::to_value(Scene::default()).unwrap();
+        if let Some(scene) = scene.as_object_mut() {
+            for field in ["lights", "ambient"] {
+                scene.insert(field.into(), default[field].clone());
+            }
+        }
+    }
+    if version < 11 {
+        // tonemapping was added, colors used to be clipped and shown as they were
+        if let Some(scene) = scene.as_object_mut() {
+            scene.insert(
+                "tonemapping".into(),
+                serde_json::to_value(Tonemapping::default()).unwrap(),
+            );
+        }
+    }
+    if version < 12 {
+        // supersampling was added
+        let default = serde_json::to_value(Camera::default()).unwrap();
+        if let Some(camera) = scene
+            .get_mut("camera")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            for field in ["samples_per_pixel", "accumulate"] {
+                camera.insert(field.into(), default[field].clone());
+            }
+        }
+    }
+    if version < 13 {
+        // camera paths were added
+        if let Some(scene) = scene.as_object_mut() {
+            scene.insert("keyframes".into(), Vec::<serde_json::Value>::new().into());
+        }
+    }
+}
+
+fn objects_mut<'a>(
+    scene: &'a mut serde_json::Value,
+    list: &str,
+) -> impl Iterator<Item = &'a mut serde_json::Map<String, serde_json::Value>> {
+    scene
+        .get_mut(list)
+        .and_then(serde_json::Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(serde_json::Value::as_object_mut)
+}
+
+impl Scene {
+    pub fn objects_info(&self) -> ObjectsInfo {
+        ObjectsInfo {
+            wormholes_count: self
+                .wormholes
+                .iter()
+                .filter(|wormhole| wormhole.sheet_distance(&self.sheets).is_some())
+                .count() as _,
+            spheres_count: self.spheres.len() as _,
+            sheets_count: self.sheets.len() as _,
+            lights_count: self.lights.len() as _,
+            ambient: self.ambient,
+        }
+    }
+
+    pub fn gpu_lights(&self) -> Vec<GpuLight> {
+        self.lights.iter().map(Light::to_gpu).collect()
+    }
+
+    /// The wormholes that can be drawn, in the layout used by the shader
+    pub fn gpu_wormholes(&self) -> Vec<GpuWormhole> {
+        self.wormholes
+            .iter()
+            .filter_map(|wormhole| wormhole.to_gpu(&self.sheets, self.throat_length))
+            .collect()
+    }
+
+    /// The signed distance to the surface, or `None` if there are no sheets for there to be a surface
+    pub fn sdf(&self) -> Option<impl Fn(Vector4<f32>) -> f32 + '_> {
+        if self.sheets.is_empty() {
+            return None;
+        }
+        let wormholes = self.gpu_wormholes();
+        Some(move |p| wormhole_sdf(&self.sheets, &wormholes, p))
+    }
+
+    pub fn gpu_camera(&self) -> GpuCamera {
+        match (self.camera.view_mode, self.sdf()) {
+            (ViewMode::Surface, Some(scene_sdf)) => self.camera.to_gpu_on_surface(scene_sdf),
+            _ => self.camera.to_gpu(),
+        }
+    }
+
+    pub fn gpu_spheres(&self) -> Vec<GpuSphere> {
+        let scene_sdf = self.sdf();
+        self.spheres
+            .iter()
+            .map(|sphere| {
+                let surface_position = match &scene_sdf {
+                    Some(scene_sdf) => sdf::project(scene_sdf, sphere.position, 0.0),
+                    None => sphere.position,
+                };
+                sphere.to_gpu(surface_position)
+            })
+            .collect()
+    }
+
+    /// Index of the sheet closest to `w`
+    pub fn nearest_sheet(&self, w: f32) -> Option<usize> {
+        self.sheets
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| f32::abs(*a - w).total_cmp(&f32::abs(*b - w)))
+            .map(|(i, _)| i)
+    }
+
+    pub fn project_spheres(&mut self) {
+        if self.sheets.is_empty() {
+            return;
+        }
+        let wormholes = self.gpu_wormholes();
+        project_spheres(
+            |p| wormhole_sdf(&self.sheets, &wormholes, p),
+            &mut self.spheres,
+        );
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&VersionedScene {
+            version: SCENE_VERSION,
+            scene: self,
+        })
+        .unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        let mut scene = serde_json::from_str::<serde_json::Value>(json)?;
+
+        let version = scene
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or(SceneError::MissingVersion)?;
+        let version = u32::try_from(version)
+            .ok()
+            .filter(|&version| version <= SCENE_VERSION)
+            .ok_or(SceneError::UnsupportedVersion(version))?;
+        migrate(&mut scene, version);
+
+        let scene: Self = serde_json::from_value(scene)?;
+        if !scene.sheets.is_sorted_by(|a, b| a < b) {
+            return Err(SceneError::UnsortedSheets);
+        }
+        Ok(scene)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
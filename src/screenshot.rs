@@ -0,0 +1,176 @@
+use crate::{cpu::Image, scene::Scene};
+use eframe::wgpu;
+use std::{
+    collections::TryReserveError,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+/// The size of the tiles that high resolution images are rendered in, which is small enough that
+/// each dispatch finishes before the GPU would time out
+pub const TILE_SIZE: u32 = 512;
+
+/// A part of an image that is rendered on its own, with `x` and `y` at its bottom left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Splits a `width` by `height` image into tiles of at most [`TILE_SIZE`], row by row
+pub fn tiles(width: u32, height: u32) -> impl Iterator<Item = Tile> {
+    (0..height).step_by(TILE_SIZE as usize).flat_map(move |y| {
+        (0..width).step_by(TILE_SIZE as usize).map(move |x| Tile {
+            x,
+            y,
+            width: TILE_SIZE.min(width - x),
+            height: TILE_SIZE.min(height - y),
+        })
+    })
+}
+
+/// The bytes in each row of a copy of a `width` wide RGBA8 texture into a buffer, which has to be
+/// a multiple of [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`]
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    Poll(wgpu::PollError),
+    Map(wgpu::BufferAsyncError),
+    OutOfMemory(TryReserveError),
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::Poll(error) => write!(f, "failed to wait for the GPU: {error}"),
+            ScreenshotError::Map(error) => write!(f, "failed to read the image back: {error}"),
+            ScreenshotError::OutOfMemory(error) => {
+                write!(f, "not enough memory for the image: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {}
+
+impl From<wgpu::PollError> for ScreenshotError {
+    fn from(error: wgpu::PollError) -> Self {
+        Self::Poll(error)
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for ScreenshotError {
+    fn from(error: wgpu::BufferAsyncError) -> Self {
+        Self::Map(error)
+    }
+}
+
+/// Removes the padding from the end of each row of a texture that was copied into a buffer, and
+/// swaps the channels back to RGBA if they were BGRA
+pub fn image_from_padded(width: u32, height: u32, bgra: bool, data: &[u8]) -> Image {
+    let pixels = data
+        .chunks(padded_bytes_per_row(width) as usize)
+        .take(height as usize)
+        .flat_map(|row| row[..width as usize * 4].chunks_exact(4))
+        .map(|pixel| {
+            if bgra {
+                [pixel[2], pixel[1], pixel[0], pixel[3]]
+            } else {
+                [pixel[0], pixel[1], pixel[2], pixel[3]]
+            }
+        })
+        .collect();
+    Image {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Copies an RGBA8 or BGRA8 texture back from the GPU, waiting for everything that was submitted
+/// before it to finish
+///
+/// The texture must have been created with [`wgpu::TextureUsages::COPY_SRC`]
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Image, ScreenshotError> {
+    let width = texture.width();
+    let height = texture.height();
+    let bytes_per_row = padded_bytes_per_row(width);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Screenshot Buffer"),
+        size: bytes_per_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Screenshot Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(core::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        // the receiver is only gone if waiting failed, which is already an error
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::PollType::Wait)?;
+    // waiting finished everything submitted, so the buffer has either been mapped or failed to be
+    receiver.try_recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+    let bgra = matches!(
+        texture.format(),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+    );
+    let image = image_from_padded(width, height, bgra, &slice.get_mapped_range());
+    buffer.unmap();
+    Ok(image)
+}
+
+/// The text chunks that are written into screenshots, so that they can be recreated later
+pub fn metadata(scene: &Scene) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "Camera",
+            serde_json::to_string_pretty(&scene.camera).unwrap(),
+        ),
+        ("Scene", scene.to_json()),
+    ]
+}
+
+/// `path` with the frame number before its extension, for each image of a sequence
+pub fn numbered_path(path: impl AsRef<Path>, frame: u32) -> PathBuf {
+    let path = path.as_ref();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}_{frame:05}.{}", extension.to_string_lossy()),
+        None => format!("{stem}_{frame:05}"),
+    };
+    path.with_file_name(name)
+}
+
+/// Writes `image` as a png, with the camera and the rest of `scene` in its metadata
+pub fn save(image: &Image, scene: &Scene, path: impl AsRef<Path>) -> io::Result<()> {
+    image.write_png_with_text(path, &metadata(scene))
+}
@@ -1,4 +1,4 @@
-use math::{Vector2, Vector3, Vector4};
+use math::Vector4;
 
 pub fn normal(mut f: impl FnMut(Vector4<f32>) -> f32, p: Vector4<f32>) -> Vector4<f32> {
     let x = Vector4 {
@@ -34,22 +34,16 @@ pub fn normal(mut f: impl FnMut(Vector4<f32>) -> f32, p: Vector4<f32>) -> Vector
     .normalised()
 }
 
-pub fn torus(p: Vector4<f32>, ring_radius: f32, radius: f32) -> f32 {
-    let position = Vector4 {
-        x: 0.0,
-        y: 0.0,
-        z: 0.0,
-        w: 0.0,
-    };
-    let q = Vector2 {
-        x: Vector3 {
-            x: p.x - position.x,
-            y: p.y - position.y,
-            z: p.z - position.z,
-        }
-        .magnitude()
-            - ring_radius,
-        y: p.w - position.w,
-    };
-    q.magnitude() - radius
+/// Moves `p` along the gradient of `f` until `f` would be `distance` if the surface were flat
+pub fn project(
+    mut f: impl FnMut(Vector4<f32>) -> f32,
+    p: Vector4<f32>,
+    distance: f32,
+) -> Vector4<f32> {
+    let offset = f(p) - distance;
+    if f32::abs(offset) > 0.0001 {
+        p - normal(f, p) * offset
+    } else {
+        p
+    }
 }
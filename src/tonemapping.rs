@@ -0,0 +1,61 @@
+use bytemuck::NoUninit;
+use serde::{Deserialize, Serialize};
+
+/// How colors brighter than white are brought back into range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Curve {
+    /// Everything brighter than white is clipped to white
+    #[default]
+    Clamp,
+    Reinhard,
+    /// John Hable's curve from Uncharted 2
+    Filmic,
+    /// Krzysztof Narkowicz's fit of the ACES reference rendering transform
+    Aces,
+}
+
+impl Curve {
+    pub const ALL: [Self; 4] = [Self::Clamp, Self::Reinhard, Self::Filmic, Self::Aces];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Clamp => "Clamp",
+            Self::Reinhard => "Reinhard",
+            Self::Filmic => "Filmic",
+            Self::Aces => "ACES",
+        }
+    }
+}
+
+/// How the HDR image that is rendered is turned into the colors that are shown
+///
+/// The default leaves colors as they are, apart from clipping them
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Tonemapping {
+    /// In stops, so each 1 doubles the brightness
+    pub exposure: f32,
+    pub curve: Curve,
+    /// Encode the colors with the sRGB transfer function, instead of showing linear colors as if
+    /// they were already encoded
+    pub srgb: bool,
+}
+
+impl Tonemapping {
+    pub fn to_gpu(&self) -> GpuTonemapping {
+        GpuTonemapping {
+            exposure: f32::exp2(self.exposure),
+            curve: self.curve as u32,
+            srgb: self.srgb as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, NoUninit)]
+#[repr(C)]
+pub struct GpuTonemapping {
+    /// What the colors are multiplied by before the curve
+    pub exposure: f32,
+    /// The index of the [`Curve`]
+    pub curve: u32,
+    pub srgb: u32,
+}
@@ -0,0 +1,96 @@
+use math::{NoE4Rotor, Vector4};
+use raytracing::{
+    animation::{self, Keyframe, Timeline},
+    camera::{Camera, Movement},
+};
+
+fn keyframe(time: f32, x: f32, angle: f32) -> Keyframe {
+    Keyframe {
+        time,
+        position: Vector4 {
+            x,
+            y: 0.0,
+            z: 0.0,
+            w: 2.0,
+        },
+        base_rotation: NoE4Rotor::rotate_xy(angle),
+        xw_rotation: angle,
+        fov: 1.0 + angle,
+    }
+}
+
+#[test]
+fn passes_through_each_keyframe() {
+    // out of order, and not evenly spaced
+    let keyframes = [
+        keyframe(3.0, 4.0, 1.0),
+        keyframe(0.0, 0.0, 0.0),
+        keyframe(1.0, 1.0, 0.5),
+    ];
+    for expected in &keyframes {
+        let sampled = animation::sample(&keyframes, expected.time).unwrap();
+        assert!((sampled.position - expected.position).magnitude() < 0.001);
+        assert!((sampled.xw_rotation - expected.xw_rotation).abs() < 0.001);
+        assert!((sampled.fov - expected.fov).abs() < 0.001);
+        let (a, b) = (sampled.base_rotation.x(), expected.base_rotation.x());
+        assert!((a - b).magnitude() < 0.001);
+    }
+    assert_eq!(animation::duration(&keyframes), 3.0);
+
+    // stays at the ends outside of the path
+    assert_eq!(animation::sample(&keyframes, -1.0).unwrap().position.x, 0.0);
+    assert_eq!(animation::sample(&keyframes, 5.0).unwrap().position.x, 4.0);
+    assert_eq!(animation::sample(&[], 1.0), None);
+}
+
+#[test]
+fn moves_smoothly_between_keyframes() {
+    let keyframes = [
+        keyframe(0.0, 0.0, 0.0),
+        keyframe(1.0, 1.0, 0.5),
+        keyframe(3.0, 4.0, 1.0),
+    ];
+    let x = |time| animation::sample(&keyframes, time).unwrap().position.x;
+
+    // the velocity is the same either side of the middle keyframe
+    let step = 0.001;
+    let before = (x(1.0) - x(1.0 - step)) / step;
+    let after = (x(1.0 + step) - x(1.0)) / step;
+    assert!((before - after).abs() < 0.01, "{before} != {after}");
+
+    // with only two keyframes it goes straight between them at a constant speed
+    assert!((animation::sample(&keyframes[..2], 0.25).unwrap().position.x - 0.25).abs() < 0.001);
+}
+
+#[test]
+fn applying_a_keyframe_flies_the_camera_there() {
+    let mut camera = Camera {
+        movement: Movement::Walk,
+        ..Camera::default()
+    };
+    let keyframe = keyframe(0.0, 3.0, 0.5);
+    keyframe.apply(&mut camera);
+    assert_eq!(Keyframe::new(0.0, &camera), keyframe);
+    assert_eq!(camera.movement, Movement::Fly);
+}
+
+#[test]
+fn timeline_stops_or_loops_at_the_end() {
+    let mut timeline = Timeline::default();
+    timeline.update(1.0, 2.0);
+    assert_eq!(timeline.time, 0.0, "only moves while playing");
+
+    timeline.playing = true;
+    timeline.update(1.5, 2.0);
+    assert_eq!(timeline.time, 1.5);
+    timeline.update(1.5, 2.0);
+    assert_eq!(timeline.time, 2.0);
+    assert!(!timeline.playing);
+
+    timeline.playing = true;
+    timeline.looping = true;
+    timeline.time = 1.5;
+    timeline.update(1.0, 2.0);
+    assert!((timeline.time - 0.5).abs() < 0.001);
+    assert!(timeline.playing);
+}
@@ -0,0 +1,130 @@
+//! Moves the camera around the default scene by simulating key presses.
+
+use eframe::egui;
+use math::Vector4;
+use raytracing::{
+    camera::{Camera, Movement},
+    objects::wormhole_sdf,
+    scene::Scene,
+};
+
+/// Holds `keys` down for `frames` frames of `ts` seconds
+fn hold_keys(scene: &mut Scene, keys: &[egui::Key], frames: usize, ts: f32) {
+    let ctx = egui::Context::default();
+    let sheets = scene.sheets.clone();
+    let wormholes = scene.gpu_wormholes();
+    for _ in 0..frames {
+        let input = egui::RawInput {
+            events: keys
+                .iter()
+                .map(|&key| egui::Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: Default::default(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            let scene_sdf = Some(|p| wormhole_sdf(&sheets, &wormholes, p));
+            scene.camera.update(ctx, ts, scene_sdf);
+        });
+    }
+}
+
+#[test]
+fn walk_through_throat() {
+    let mut scene = Scene {
+        camera: Camera {
+            movement: Movement::Walk,
+            ..Camera::new(Vector4 {
+                x: -12.0,
+                y: 0.0,
+                z: 0.0,
+                w: 5.0,
+            })
+        },
+        ..Default::default()
+    };
+
+    // along the top of the upper sheet, down the throat, and back out along the bottom of the lower sheet
+    hold_keys(&mut scene, &[egui::Key::W], 300, 0.02);
+
+    let camera = &scene.camera;
+    assert!(
+        f32::abs(camera.position.w + 5.0) < 0.01,
+        "camera should be one eye height below the lower sheet, but is at {:?}",
+        camera.position,
+    );
+    assert!(camera.position.x < -10.0);
+    let ana = camera.walk_rotation.w();
+    assert!(
+        ana.w < -0.99,
+        "ana should point away from the lower sheet, but is {ana:?}"
+    );
+    let forward = camera.walk_rotation.x();
+    assert!(
+        forward.x < -0.99,
+        "the camera should be walking away from the wormhole, but is facing {forward:?}"
+    );
+}
+
+#[test]
+fn walk_on_the_inside() {
+    let mut scene = Scene {
+        camera: Camera {
+            movement: Movement::Walk,
+            ..Camera::new(Vector4 {
+                x: -12.0,
+                y: 0.0,
+                z: 0.0,
+                w: 3.0,
+            })
+        },
+        ..Default::default()
+    };
+
+    // between the sheets the surface goes around the outside of the throat instead
+    hold_keys(&mut scene, &[egui::Key::W], 300, 0.02);
+
+    let camera = &scene.camera;
+    assert!(
+        f32::abs(camera.position.w + 3.0) < 0.01,
+        "camera should be one eye height above the lower sheet, but is at {:?}",
+        camera.position,
+    );
+    assert!(camera.walk_rotation.w().w > 0.99);
+}
+
+#[test]
+fn fly_into_sheet() {
+    let mut scene = Scene {
+        camera: Camera {
+            collision: true,
+            ..Camera::new(Vector4 {
+                x: -20.0,
+                y: 0.0,
+                z: 0.0,
+                w: 2.0,
+            })
+        },
+        ..Default::default()
+    };
+
+    // flying forwards and up into the upper sheet slides along it
+    hold_keys(&mut scene, &[egui::Key::W, egui::Key::E], 100, 0.02);
+
+    let camera = &scene.camera;
+    let distance = scene.sdf().unwrap()(camera.position);
+    assert!(
+        f32::abs(distance + camera.min_distance) < 0.01,
+        "camera should be the minimum distance from the upper sheet, but is {distance} from the surface at {:?}",
+        camera.position,
+    );
+    assert!(
+        camera.position.x > -15.0,
+        "camera should have slid along the sheet"
+    );
+}
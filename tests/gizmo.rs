@@ -0,0 +1,120 @@
+//! Drags the gizmo handles by simulating the mouse.
+
+use eframe::egui;
+use math::Vector4;
+use raytracing::{gizmo, objects::Sphere, picking, scene::Scene};
+
+const SCREEN: egui::Rect = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(800.0, 600.0));
+
+/// Presses the mouse at `from` and drags it to `to` over a few frames, with the sphere gizmo for
+/// the first sphere covering the whole screen
+fn drag(scene: &mut Scene, from: egui::Pos2, to: egui::Pos2) {
+    let ctx = egui::Context::default();
+    let camera = scene.gpu_camera();
+    let button = |pos, pressed| egui::Event::PointerButton {
+        pos,
+        button: egui::PointerButton::Primary,
+        pressed,
+        modifiers: Default::default(),
+    };
+
+    let mut frames = vec![
+        vec![egui::Event::PointerMoved(from)],
+        vec![button(from, true)],
+    ];
+    for i in 1..=4 {
+        let pos = from + (to - from) * (i as f32 / 4.0);
+        frames.push(vec![egui::Event::PointerMoved(pos)]);
+    }
+    frames.push(vec![button(to, false)]);
+
+    for events in frames {
+        let input = egui::RawInput {
+            screen_rect: Some(SCREEN),
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::NONE)
+                .show(ctx, |ui| {
+                    gizmo::sphere(ui, SCREEN, &camera, &mut scene.spheres[0]);
+                });
+        });
+    }
+}
+
+#[test]
+fn drag_along_axis() {
+    // the sphere's z axis points to the right of the default camera
+    let position = Vector4 {
+        x: 2.0,
+        y: 0.0,
+        z: 0.0,
+        w: 2.0,
+    };
+    let mut scene = Scene {
+        spheres: vec![Sphere {
+            position,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let [x, y] = picking::screen_position(
+        &scene.gpu_camera(),
+        position,
+        SCREEN.width(),
+        SCREEN.height(),
+    )
+    .unwrap();
+    let centre = egui::pos2(x, SCREEN.height() - y);
+    let handle = centre + egui::vec2(80.0, 0.0);
+    drag(&mut scene, handle, handle + egui::vec2(40.0, 0.0));
+
+    let moved = scene.spheres[0].position - position;
+    assert!(moved.z > 0.5, "{moved:?}");
+    assert!(
+        moved.x.abs() < 0.001 && moved.y.abs() < 0.001 && moved.w.abs() < 0.001,
+        "{moved:?}"
+    );
+}
+
+#[test]
+fn drag_rotation_arc() {
+    let position = Vector4 {
+        x: 2.0,
+        y: 0.0,
+        z: 0.0,
+        w: 2.0,
+    };
+    let mut scene = Scene {
+        spheres: vec![Sphere {
+            position,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let [x, y] = picking::screen_position(
+        &scene.gpu_camera(),
+        position,
+        SCREEN.width(),
+        SCREEN.height(),
+    )
+    .unwrap();
+    let centre = egui::pos2(x, SCREEN.height() - y);
+    // the sphere's z axis points to the right and its w axis points up, so the knob of the zw arc is
+    // halfway between them, 48 points out, and turning z towards w moves it up and to the left
+    let knob = centre + egui::vec2(1.0, -1.0) * 48.0 * std::f32::consts::FRAC_1_SQRT_2;
+    drag(&mut scene, knob, knob + egui::vec2(-20.0, -20.0));
+
+    let angles = scene.spheres[0].rotation.plane_angles();
+    assert!(angles[5] > 0.1, "{angles:?}");
+    for (plane, angle) in angles.into_iter().enumerate() {
+        if plane != 5 {
+            assert!(angle.abs() < 0.001, "{angles:?}");
+        }
+    }
+    assert_eq!(scene.spheres[0].position, position);
+}
@@ -0,0 +1,515 @@
+//! Renders a set of canonical scenes with the CPU renderer and compares them against the
+//! reference images in `tests/golden/`.
+//!
+//! After an intentional change to the rendering, regenerate the references with
+//! `UPDATE_GOLDEN=1 cargo test --test golden` and check the new images before committing them.
+
+use math::{NoE4Rotor, Rotor, Vector3, Vector4};
+use raytracing::{
+    camera::{Camera, ViewMode},
+    cpu::{self, Image},
+    lights::Light,
+    objects::{Sphere, Wormhole},
+    scene::Scene,
+    tonemapping::{Curve, Tonemapping},
+};
+use std::{f32::consts::TAU, path::PathBuf};
+
+const WIDTH: u32 = 96;
+const HEIGHT: u32 = 64;
+
+/// The largest difference allowed in any channel before a pixel counts as mismatched
+const CHANNEL_TOLERANCE: u8 = 2;
+/// The fraction of pixels allowed to mismatch, so that a ray landing on the other side of a
+/// silhouette because of floating point differences doesn't fail the test
+const MAX_MISMATCHED_FRACTION: f32 = 0.002;
+
+fn check_golden(name: &str, scene: &Scene) {
+    check_golden_image(name, cpu::render(scene, WIDTH, HEIGHT));
+}
+
+fn check_golden_image(name: &str, actual: Image) {
+    let reference_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+        .with_extension("png");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+        actual.write_png(&reference_path).unwrap();
+        return;
+    }
+
+    let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).unwrap();
+    let actual_path = output_dir.join(format!("{name}.actual.png"));
+    let diff_path = output_dir.join(format!("{name}.diff.png"));
+
+    let reference = match Image::read_png(&reference_path) {
+        Ok(reference) => reference,
+        Err(error) => {
+            actual.write_png(&actual_path).unwrap();
+            panic!(
+                "could not read reference image {}: {error}\nthe rendered image was written to {}",
+                reference_path.display(),
+                actual_path.display(),
+            );
+        }
+    };
+    assert_eq!(
+        (reference.width, reference.height),
+        (actual.width, actual.height),
+        "reference image {} has the wrong size",
+        reference_path.display(),
+    );
+
+    let mut mismatched = 0;
+    let diff = Image {
+        width: actual.width,
+        height: actual.height,
+        pixels: actual
+            .pixels
+            .iter()
+            .zip(&reference.pixels)
+            .map(|(actual, reference)| {
+                let difference = (0..4)
+                    .map(|i| actual[i].abs_diff(reference[i]))
+                    .max()
+                    .unwrap();
+                if difference > CHANNEL_TOLERANCE {
+                    mismatched += 1;
+                    [255, 0, 0, 255]
+                } else {
+                    // a dimmed version of the image so that mismatches stand out
+                    actual.map(|c| c / 4)
+                }
+            })
+            .collect(),
+    };
+
+    let mismatched_fraction = mismatched as f32 / actual.pixels.len() as f32;
+    if mismatched_fraction > MAX_MISMATCHED_FRACTION {
+        actual.write_png(&actual_path).unwrap();
+        diff.write_png(&diff_path).unwrap();
+        panic!(
+            "{mismatched} pixels ({:.2}%) differ from {} by more than {CHANNEL_TOLERANCE}\nthe rendered image was written to {}\nthe mismatched pixels are shown in red in {}",
+            mismatched_fraction * 100.0,
+            reference_path.display(),
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+/// A point light at the camera, so that everything the camera can see is lit no matter which side
+/// of the sheets it is on
+fn headlight(camera: &Camera, intensity: f32) -> Vec<Light> {
+    vec![Light {
+        vector: camera.position,
+        intensity,
+        ..Default::default()
+    }]
+}
+
+#[test]
+fn default_scene() {
+    let mut scene = Scene::default();
+    scene.project_spheres();
+    check_golden("default_scene", &scene);
+}
+
+#[test]
+fn rotated_camera() {
+    let scene = Scene {
+        camera: Camera {
+            base_rotation: NoE4Rotor::rotate_xz(TAU * 0.1).then(NoE4Rotor::rotate_xy(TAU * 0.05)),
+            xw_rotation: -TAU * 0.08,
+            fov: TAU * 0.3,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    check_golden("rotated_camera", &scene);
+}
+
+#[test]
+fn down_the_throat() {
+    let mut camera = Camera::new(Vector4 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        w: -2.0,
+    });
+    camera.xw_rotation = TAU * 0.25;
+    let scene = Scene {
+        lights: headlight(&camera, 100.0),
+        camera,
+        spheres: vec![],
+        ..Default::default()
+    };
+    check_golden("down_the_throat", &scene);
+}
+
+#[test]
+fn multiple_wormholes() {
+    let mut camera = Camera::new(Vector4 {
+        x: -12.0,
+        y: 0.0,
+        z: 0.0,
+        w: 3.0,
+    });
+    camera.xw_rotation = -TAU * 0.05;
+    let scene = Scene {
+        lights: headlight(&camera, 100.0),
+        camera,
+        wormholes: vec![
+            Wormhole {
+                position: Vector3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: -5.0,
+                },
+                throat_size: 1.5,
+                ..Default::default()
+            },
+            Wormhole {
+                position: Vector3 {
+                    x: 4.0,
+                    y: 0.0,
+                    z: 6.0,
+                },
+                throat_size: 2.5,
+                ..Default::default()
+            },
+        ],
+        spheres: vec![],
+        ..Default::default()
+    };
+    check_golden("multiple_wormholes", &scene);
+}
+
+#[test]
+fn short_throat() {
+    let mut camera = Camera::new(Vector4 {
+        x: -8.0,
+        y: 0.0,
+        z: 0.0,
+        w: 0.5,
+    });
+    camera.xw_rotation = -TAU * 0.03;
+    let mut scene = Scene {
+        lights: headlight(&camera, 100.0),
+        camera,
+        sheets: vec![-1.5, 1.5],
+        throat_length: 1.5,
+        ..Default::default()
+    };
+    scene.project_spheres();
+    check_golden("short_throat", &scene);
+}
+
+#[test]
+fn long_throat() {
+    let mut camera = Camera::new(Vector4 {
+        x: -8.0,
+        y: 0.0,
+        z: 0.0,
+        w: 6.0,
+    });
+    camera.xw_rotation = -TAU * 0.06;
+    let mut scene = Scene {
+        camera,
+        sheets: vec![-8.0, 8.0],
+        throat_length: 2.0,
+        ..Default::default()
+    };
+    scene.project_spheres();
+    check_golden("long_throat", &scene);
+}
+
+#[test]
+fn offset_wormhole() {
+    let mut camera = Camera::new(Vector4 {
+        x: -14.0,
+        y: 0.0,
+        z: 0.0,
+        w: 0.0,
+    });
+    camera.fov = TAU * 0.3;
+    let scene = Scene {
+        lights: headlight(&camera, 100.0),
+        camera,
+        wormholes: vec![Wormhole {
+            position: Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: -3.0,
+            },
+            other_position: Some(Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 4.0,
+            }),
+            throat_size: 1.0,
+            throat_length: Some(2.0),
+            ..Default::default()
+        }],
+        spheres: vec![],
+        ..Default::default()
+    };
+    check_golden("offset_wormhole", &scene);
+}
+
+#[test]
+fn oriented_sphere() {
+    let scene = Scene {
+        spheres: vec![Sphere {
+            position: Vector4 {
+                x: -1.8,
+                y: 0.1,
+                z: 0.0,
+                w: 2.0,
+            },
+            rotation: Rotor::rotate_xy(TAU * 0.1).then(Rotor::rotate_zw(TAU * 0.15)),
+            radius: 0.6,
+            color: Vector3 {
+                x: 1.0,
+                y: 0.5,
+                z: 0.2,
+            },
+            pattern_scale: 40.0,
+            ..Default::default()
+        }],
+        lights: headlight(&Camera::default(), 2.0),
+        ..Default::default()
+    };
+    check_golden("oriented_sphere", &scene);
+}
+
+#[test]
+fn stacked_sheets() {
+    let mut camera = Camera::new(Vector4 {
+        x: -14.0,
+        y: 0.0,
+        z: 0.0,
+        w: 0.0,
+    });
+    camera.xw_rotation = -TAU * 0.06;
+    camera.fov = TAU * 0.3;
+    let scene = Scene {
+        lights: headlight(&camera, 100.0),
+        camera,
+        sheets: vec![-6.0, -2.0, 2.0, 6.0],
+        throat_length: 1.0,
+        wormholes: vec![
+            Wormhole {
+                sheet: 0,
+                position: Vector3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: -6.0,
+                },
+                throat_size: 1.5,
+                ..Default::default()
+            },
+            Wormhole {
+                sheet: 1,
+                throat_size: 2.0,
+                ..Default::default()
+            },
+            Wormhole {
+                sheet: 2,
+                position: Vector3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 6.0,
+                },
+                throat_size: 1.5,
+                ..Default::default()
+            },
+        ],
+        spheres: vec![],
+        ..Default::default()
+    };
+    check_golden("stacked_sheets", &scene);
+}
+
+#[test]
+fn surface_view() {
+    let mut scene = Scene {
+        camera: Camera {
+            view_mode: ViewMode::Surface,
+            ..Camera::new(Vector4 {
+                x: -9.0,
+                y: 0.0,
+                z: 0.0,
+                w: 4.0,
+            })
+        },
+        spheres: vec![Sphere {
+            position: Vector4 {
+                x: -5.0,
+                y: 0.0,
+                z: -2.0,
+                w: 4.0,
+            },
+            radius: 1.0,
+            pattern_scale: 10.0,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    scene.project_spheres();
+    check_golden("surface_view", &scene);
+}
+
+#[test]
+fn sphere_shadow() {
+    let mut camera = Camera::new(Vector4 {
+        x: 4.0,
+        y: 0.0,
+        z: 0.0,
+        w: 7.0,
+    });
+    camera.xw_rotation = -TAU * 0.1;
+    let mut scene = Scene {
+        camera,
+        spheres: vec![Sphere {
+            position: Vector4 {
+                x: 8.0,
+                y: 0.0,
+                z: 0.0,
+                w: 5.0,
+            },
+            radius: 1.0,
+            pattern_scale: 10.0,
+            ..Default::default()
+        }],
+        lights: vec![Light {
+            vector: Vector4 {
+                x: 9.0,
+                y: 0.0,
+                z: 2.0,
+                w: 9.0,
+            },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    scene.project_spheres();
+    check_golden("sphere_shadow", &scene);
+}
+
+#[test]
+fn path_traced() {
+    let mut camera = Camera::new(Vector4 {
+        x: 4.0,
+        y: 0.0,
+        z: 0.0,
+        w: 7.0,
+    });
+    camera.xw_rotation = -TAU * 0.1;
+    let mut scene = Scene {
+        camera,
+        spheres: vec![Sphere {
+            position: Vector4 {
+                x: 8.0,
+                y: 0.0,
+                z: 0.0,
+                w: 5.0,
+            },
+            radius: 1.0,
+            color: Vector3 {
+                x: 1.0,
+                y: 0.3,
+                z: 0.2,
+            },
+            pattern_scale: 10.0,
+            ..Default::default()
+        }],
+        lights: vec![Light {
+            vector: Vector4 {
+                x: 9.0,
+                y: 0.0,
+                z: 2.0,
+                w: 9.0,
+            },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    scene.project_spheres();
+    check_golden_image(
+        "path_traced",
+        cpu::render_path_traced(&scene, WIDTH, HEIGHT, 16, 2),
+    );
+}
+
+#[test]
+fn tonemapped() {
+    let mut camera = Camera::new(Vector4 {
+        x: 4.0,
+        y: 0.0,
+        z: 0.0,
+        w: 7.0,
+    });
+    camera.xw_rotation = -TAU * 0.1;
+    let mut scene = Scene {
+        camera,
+        lights: vec![Light {
+            vector: Vector4 {
+                x: 8.0,
+                y: 0.0,
+                z: 0.0,
+                w: 6.0,
+            },
+            intensity: 20.0,
+            ..Default::default()
+        }],
+        tonemapping: Tonemapping {
+            exposure: 1.0,
+            curve: Curve::Aces,
+            srgb: true,
+        },
+        ..Default::default()
+    };
+    scene.project_spheres();
+    check_golden("tonemapped", &scene);
+}
+
+#[test]
+fn supersampled() {
+    let mut camera = Camera::new(Vector4 {
+        x: 4.0,
+        y: 0.0,
+        z: 0.0,
+        w: 7.0,
+    });
+    camera.xw_rotation = -TAU * 0.1;
+    camera.samples_per_pixel = 9;
+    let mut scene = Scene {
+        camera,
+        spheres: vec![Sphere {
+            position: Vector4 {
+                x: 8.0,
+                y: 0.0,
+                z: 0.0,
+                w: 5.0,
+            },
+            radius: 1.0,
+            pattern_scale: 10.0,
+            ..Default::default()
+        }],
+        lights: vec![Light {
+            vector: Vector4 {
+                x: 9.0,
+                y: 0.0,
+                z: 2.0,
+                w: 9.0,
+            },
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    scene.project_spheres();
+    check_golden("supersampled", &scene);
+}
@@ -0,0 +1,165 @@
+//! Records edits the way the app does, by comparing objects before and after their widgets.
+
+use eframe::egui;
+use raytracing::{
+    history::{Edit, History, ListEdit},
+    objects::Sphere,
+    scene::Scene,
+};
+
+const SCREEN: egui::Rect = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(400.0, 300.0));
+
+/// Runs a frame with a radius `DragValue` for the first sphere in the top left corner, recording
+/// any change to it
+fn frame(ctx: &egui::Context, scene: &mut Scene, history: &mut History, events: Vec<egui::Event>) {
+    let input = egui::RawInput {
+        screen_rect: Some(SCREEN),
+        modifiers: events
+            .iter()
+            .find_map(|event| match event {
+                egui::Event::Key { modifiers, .. } => Some(*modifiers),
+                _ => None,
+            })
+            .unwrap_or_default(),
+        events,
+        ..Default::default()
+    };
+    let _ = ctx.run(input, |ctx| {
+        history.update(ctx, scene);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let before = scene.spheres[0];
+            ui.add(egui::DragValue::new(&mut scene.spheres[0].radius).speed(0.01));
+            if scene.spheres[0] != before {
+                let edit = ListEdit::Change {
+                    index: 0,
+                    before,
+                    after: scene.spheres[0],
+                };
+                history.push(ctx, Edit::Sphere(edit));
+            }
+        });
+    });
+}
+
+/// Drags the `DragValue` to the right over a few frames
+fn drag(ctx: &egui::Context, scene: &mut Scene, history: &mut History) {
+    let from = egui::pos2(20.0, 15.0);
+    let button = |pos, pressed| egui::Event::PointerButton {
+        pos,
+        button: egui::PointerButton::Primary,
+        pressed,
+        modifiers: Default::default(),
+    };
+
+    frame(ctx, scene, history, vec![egui::Event::PointerMoved(from)]);
+    frame(ctx, scene, history, vec![button(from, true)]);
+    for i in 1..=5 {
+        let pos = from + egui::vec2(10.0 * i as f32, 0.0);
+        frame(ctx, scene, history, vec![egui::Event::PointerMoved(pos)]);
+    }
+    frame(
+        ctx,
+        scene,
+        history,
+        vec![button(from + egui::vec2(50.0, 0.0), false)],
+    );
+    frame(ctx, scene, history, vec![]);
+}
+
+fn undo(ctx: &egui::Context, scene: &mut Scene, history: &mut History) {
+    frame(
+        ctx,
+        scene,
+        history,
+        vec![egui::Event::Key {
+            key: egui::Key::Z,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::COMMAND,
+        }],
+    );
+}
+
+#[test]
+fn drags_are_single_steps() {
+    let ctx = egui::Context::default();
+    let mut scene = Scene::default();
+    let mut history = History::default();
+    let radius = scene.spheres[0].radius;
+
+    drag(&ctx, &mut scene, &mut history);
+    let dragged_once = scene.spheres[0].radius;
+    assert!(dragged_once > radius);
+    drag(&ctx, &mut scene, &mut history);
+    assert!(scene.spheres[0].radius > dragged_once);
+
+    undo(&ctx, &mut scene, &mut history);
+    assert_eq!(scene.spheres[0].radius, dragged_once);
+    undo(&ctx, &mut scene, &mut history);
+    assert_eq!(scene.spheres[0].radius, radius);
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn undo_and_redo_add_and_remove() {
+    let ctx = egui::Context::default();
+    let mut scene = Scene::default();
+    let mut history = History::default();
+
+    let added = Sphere {
+        radius: 2.0,
+        ..Default::default()
+    };
+    scene.spheres.push(added);
+    history.push(
+        &ctx,
+        Edit::Sphere(ListEdit::Add {
+            index: 1,
+            item: added,
+        }),
+    );
+    let item = scene.spheres.remove(0);
+    history.push(&ctx, Edit::Sphere(ListEdit::Remove { index: 0, item }));
+    assert_eq!(scene.spheres, [added]);
+
+    history.undo(&mut scene);
+    assert_eq!(scene.spheres, [item, added]);
+    history.undo(&mut scene);
+    assert_eq!(scene.spheres, [item]);
+    assert!(!history.can_undo());
+
+    history.redo(&mut scene);
+    history.redo(&mut scene);
+    assert_eq!(scene.spheres, [added]);
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn undo_and_redo_light_changes() {
+    let ctx = egui::Context::default();
+    let mut scene = Scene::default();
+    let mut history = History::default();
+
+    let before = scene.lights[0];
+    scene.lights[0].intensity = 5.0;
+    history.push(
+        &ctx,
+        Edit::Light(ListEdit::Change {
+            index: 0,
+            before,
+            after: scene.lights[0],
+        }),
+    );
+    let item = scene.lights.remove(0);
+    history.push(&ctx, Edit::Light(ListEdit::Remove { index: 0, item }));
+    assert!(scene.lights.is_empty());
+
+    history.undo(&mut scene);
+    assert_eq!(scene.lights, [item]);
+    history.undo(&mut scene);
+    assert_eq!(scene.lights, [before]);
+
+    history.redo(&mut scene);
+    assert_eq!(scene.lights[0].intensity, 5.0);
+}
@@ -0,0 +1,35 @@
+use math::Vector4;
+use raytracing::{path_tracing::PathTracer, scene::Scene};
+
+#[test]
+fn accumulates_until_anything_changes() {
+    let mut scene = Scene::default();
+    let mut path_tracer = PathTracer::default();
+
+    let indices = (0..3)
+        .map(|_| path_tracer.next_frame(&scene, 64, 48).index)
+        .collect::<Vec<_>>();
+    assert_eq!(indices, [0, 1, 2]);
+    assert_eq!(path_tracer.frames(), 3);
+
+    scene.camera.position += Vector4 {
+        x: 0.1,
+        y: 0.0,
+        z: 0.0,
+        w: 0.0,
+    };
+    assert_eq!(path_tracer.next_frame(&scene, 64, 48).index, 0);
+    assert_eq!(path_tracer.next_frame(&scene, 64, 48).index, 1);
+
+    scene.lights[0].intensity *= 2.0;
+    assert_eq!(path_tracer.next_frame(&scene, 64, 48).index, 0);
+
+    assert_eq!(path_tracer.next_frame(&scene, 32, 48).index, 0);
+
+    path_tracer.max_bounces += 1;
+    assert_eq!(path_tracer.next_frame(&scene, 32, 48).index, 0);
+
+    path_tracer.restart();
+    assert_eq!(path_tracer.next_frame(&scene, 32, 48).index, 0);
+    assert_eq!(path_tracer.next_frame(&scene, 32, 48).index, 1);
+}
@@ -0,0 +1,105 @@
+use math::Vector4;
+use raytracing::{
+    objects::Sphere,
+    physics::{self, DEFAULT_GRAVITY, Simulation, TIMESTEP},
+    scene::Scene,
+};
+
+fn sphere(position: Vector4<f32>, velocity: Vector4<f32>) -> Sphere {
+    Sphere {
+        position,
+        velocity,
+        radius: 0.5,
+        ..Default::default()
+    }
+}
+
+fn vector(x: f32, y: f32, z: f32, w: f32) -> Vector4<f32> {
+    Vector4 { x, y, z, w }
+}
+
+#[test]
+fn falls_through_throat() {
+    let mut scene = Scene {
+        spheres: vec![sphere(
+            vector(-9.0, 0.0, 0.0, 4.5),
+            vector(2.0, 0.0, 0.0, 0.0),
+        )],
+        gravity: Some(DEFAULT_GRAVITY),
+        ..Default::default()
+    };
+    scene.project_spheres();
+
+    for _ in 0..(5.0 / TIMESTEP) as usize {
+        physics::step(&mut scene);
+    }
+
+    // out the bottom of the lower sheet, and back the way it came
+    let sphere = &scene.spheres[0];
+    assert!(
+        (sphere.position.w + 4.5).abs() < 0.05,
+        "{:?}",
+        sphere.position
+    );
+    assert!(sphere.position.x < -10.0, "{:?}", sphere.position);
+    assert!(sphere.velocity.x < 0.0, "{:?}", sphere.velocity);
+    assert!(sphere.velocity.w.abs() < 0.01, "{:?}", sphere.velocity);
+}
+
+#[test]
+fn spheres_bounce() {
+    let mut scene = Scene {
+        spheres: vec![
+            sphere(vector(-20.0, 0.0, 10.0, 4.5), vector(0.0, 0.0, 1.0, 0.0)),
+            sphere(vector(-20.0, 0.0, 12.0, 4.5), vector(0.0, 0.0, 0.0, 0.0)),
+        ],
+        ..Default::default()
+    };
+
+    for _ in 0..(2.0 / TIMESTEP) as usize {
+        physics::step(&mut scene);
+    }
+
+    // equal masses so nearly all of the speed is handed over
+    let [a, b] = &scene.spheres[..] else {
+        unreachable!()
+    };
+    assert!(a.velocity.z.abs() < 0.1, "{:?}", a.velocity);
+    assert!(b.velocity.z > 0.9, "{:?}", b.velocity);
+    assert!((b.position - a.position).magnitude() >= a.radius + b.radius - 0.001);
+}
+
+#[test]
+fn independent_of_frame_rate() {
+    let new_scene = || Scene {
+        spheres: vec![sphere(
+            vector(-7.5, 0.0, 0.0, 4.5),
+            vector(1.0, 0.0, 0.5, 0.0),
+        )],
+        gravity: Some(DEFAULT_GRAVITY),
+        ..Default::default()
+    };
+    // half a step over so that rounding can't change how many steps fit
+    let duration = 20.5 * TIMESTEP;
+
+    let mut one_frame = new_scene();
+    let mut simulation = Simulation::default();
+    simulation.playing = true;
+    simulation.update(&mut one_frame, duration);
+
+    let mut many_frames = new_scene();
+    let mut simulation = Simulation::default();
+    simulation.playing = true;
+    for _ in 0..7 {
+        simulation.update(&mut many_frames, duration / 7.0);
+    }
+
+    assert_eq!(
+        one_frame.spheres[0].position,
+        many_frames.spheres[0].position
+    );
+    assert_eq!(
+        one_frame.spheres[0].velocity,
+        many_frames.spheres[0].velocity
+    );
+}
@@ -0,0 +1,84 @@
+use math::Vector4;
+use raytracing::{
+    camera::Camera,
+    objects::Sphere,
+    picking::{self, Object},
+    scene::Scene,
+};
+
+const SIZE: u32 = 9;
+
+fn vector(x: f32, y: f32, z: f32, w: f32) -> Vector4<f32> {
+    Vector4 { x, y, z, w }
+}
+
+fn assert_near(actual: Vector4<f32>, expected: Vector4<f32>) {
+    assert!(
+        (actual - expected).magnitude() < 0.01,
+        "expected {expected:?}, got {actual:?}"
+    );
+}
+
+#[test]
+fn picks_sphere() {
+    let scene = Scene {
+        spheres: vec![Sphere {
+            position: vector(-1.5, 0.0, 0.0, 2.0),
+            radius: 0.5,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let pick = picking::pick(&scene, SIZE / 2, SIZE / 2, SIZE, SIZE).unwrap();
+    assert_eq!(pick.object, Some(Object::Sphere(0)));
+    assert_near(pick.position, vector(-2.0, 0.0, 0.0, 2.0));
+    assert_near(pick.normal, vector(-1.0, 0.0, 0.0, 0.0));
+
+    let [x, y] = picking::screen_position(
+        &scene.gpu_camera(),
+        scene.spheres[0].position,
+        SIZE as f32,
+        SIZE as f32,
+    )
+    .unwrap();
+    assert!((x - 4.5).abs() < 0.001 && (y - 4.5).abs() < 0.001);
+}
+
+#[test]
+fn picks_wormhole_and_sheet() {
+    let scene = Scene {
+        camera: Camera::new(vector(-20.0, 0.0, 0.0, 0.0)),
+        spheres: vec![],
+        ..Default::default()
+    };
+
+    // straight ahead is the outside of the throat
+    let pick = picking::pick(&scene, SIZE / 2, SIZE / 2, SIZE, SIZE).unwrap();
+    assert_eq!(pick.object, Some(Object::Wormhole(0)));
+    assert_near(pick.position, vector(-3.0, 0.0, 0.0, 0.0));
+    assert_near(pick.normal, vector(-1.0, 0.0, 0.0, 0.0));
+
+    // the top of the view is the upper sheet, well away from the wormhole
+    let pick = picking::pick(&scene, SIZE / 2, SIZE - 1, SIZE, SIZE).unwrap();
+    assert_eq!(pick.object, None);
+    assert!((pick.position.w - 4.0).abs() < 0.01, "{:?}", pick.position);
+    assert_near(pick.normal, vector(0.0, 0.0, 0.0, -1.0));
+}
+
+#[test]
+fn selection_follows_removal() {
+    assert_eq!(
+        Object::Sphere(3).after_removing(Object::Sphere(1)),
+        Some(Object::Sphere(2))
+    );
+    assert_eq!(Object::Sphere(1).after_removing(Object::Sphere(1)), None);
+    assert_eq!(
+        Object::Sphere(0).after_removing(Object::Sphere(1)),
+        Some(Object::Sphere(0))
+    );
+    assert_eq!(
+        Object::Wormhole(2).after_removing(Object::Sphere(0)),
+        Some(Object::Wormhole(2))
+    );
+}
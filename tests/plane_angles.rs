@@ -0,0 +1,48 @@
+use math::{Rotor, Vector4};
+
+fn assert_same_rotation(a: Rotor, b: Rotor) {
+    for (a, b) in [
+        (a.x(), b.x()),
+        (a.y(), b.y()),
+        (a.z(), b.z()),
+        (a.w(), b.w()),
+    ] {
+        assert!((a - b).magnitude() < 0.001, "{a:?} != {b:?}");
+    }
+}
+
+#[test]
+fn angles_round_trip() {
+    for angles in [
+        [0.0; 6],
+        [0.3, -0.4, 1.2, 2.5, -0.7, -2.9],
+        [-3.0, 1.5, -1.5, 0.1, 0.2, 3.1],
+    ] {
+        let decomposed = Rotor::from_plane_angles(angles).plane_angles();
+        for (a, b) in angles.iter().zip(decomposed) {
+            assert!((a - b).abs() < 0.001, "{angles:?} != {decomposed:?}");
+        }
+    }
+}
+
+#[test]
+fn decomposes_any_rotation() {
+    let rotation = Rotor::rotate_xz(0.7)
+        .then(Rotor::rotate_zw(0.3))
+        .then(Rotor::rotate_xy(-2.0))
+        .then(Rotor::from_to_vector(
+            Vector4 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+            Vector4 {
+                x: 0.5,
+                y: -0.5,
+                z: 0.5,
+                w: 0.5,
+            },
+        ));
+    assert_same_rotation(Rotor::from_plane_angles(rotation.plane_angles()), rotation);
+}
@@ -0,0 +1,72 @@
+use eframe::wgpu;
+use raytracing::render_scale::{self, MAX_SCALE, MIN_SCALE, RenderScale};
+
+#[test]
+fn resolution_is_scaled_and_never_empty() {
+    let mut render_scale = RenderScale::default();
+    assert_eq!(render_scale.resolution(640, 481, u64::MAX), (640, 481));
+
+    render_scale.scale = 0.5;
+    assert_eq!(render_scale.resolution(640, 481, u64::MAX), (320, 241));
+
+    render_scale.scale = MIN_SCALE;
+    assert_eq!(render_scale.resolution(1, 2, u64::MAX), (1, 1));
+}
+
+#[test]
+fn resolution_fits_in_the_device_limits() {
+    let limits = wgpu::Limits {
+        max_buffer_size: 1 << 30,
+        max_storage_buffer_binding_size: 16 * 1_000_000,
+        ..Default::default()
+    };
+    let max_pixels = render_scale::max_pixels(&limits);
+    assert_eq!(max_pixels, 1_000_000);
+
+    let mut render_scale = RenderScale::default();
+    assert_eq!(
+        render_scale.resolution(1000, 1000, max_pixels),
+        (1000, 1000)
+    );
+
+    // shrunk to fit, keeping the aspect ratio
+    render_scale.scale = MAX_SCALE;
+    let (width, height) = render_scale.resolution(2000, 1000, max_pixels);
+    assert!(width as u64 * height as u64 <= max_pixels);
+    assert_eq!((width, height), (1414, 707));
+}
+
+#[test]
+fn automatic_scale_follows_frame_rate() {
+    let mut render_scale = RenderScale::default();
+    render_scale.target_fps = 60.0;
+    for _ in 0..60 {
+        render_scale.update(1.0 / 20.0);
+    }
+    assert_eq!(render_scale.scale, 1.0, "only changes when automatic");
+
+    render_scale.automatic = true;
+    for _ in 0..60 {
+        render_scale.update(1.0 / 20.0);
+    }
+    assert!(render_scale.scale < 1.0);
+    for _ in 0..600 {
+        render_scale.update(1.0 / 20.0);
+    }
+    assert_eq!(render_scale.scale, MIN_SCALE);
+
+    for _ in 0..6000 {
+        render_scale.update(1.0 / 200.0);
+    }
+    assert_eq!(render_scale.scale, MAX_SCALE);
+
+    let mut render_scale = RenderScale::default();
+    render_scale.automatic = true;
+    for _ in 0..600 {
+        render_scale.update(1.0 / 63.0);
+    }
+    assert_eq!(
+        render_scale.scale, 1.0,
+        "close enough to the target is left alone"
+    );
+}
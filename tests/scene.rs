@@ -0,0 +1,174 @@
+use math::{NoE4Rotor, Vector3, Vector4};
+use raytracing::{
+    animation::Keyframe,
+    camera::{Camera, ViewMode},
+    lights::Light,
+    objects::{Sphere, Wormhole},
+    scene::{SCENE_VERSION, Scene, SceneError},
+    tonemapping::Tonemapping,
+};
+use std::path::PathBuf;
+
+#[test]
+fn scenes_round_trip_through_json() {
+    let mut scene = Scene {
+        sheets: vec![-4.0, 1.0, 6.0],
+        throat_length: 2.5,
+        gravity: Some(Vector4 {
+            x: 0.0,
+            y: -9.8,
+            z: 0.0,
+            w: 0.0,
+        }),
+        ambient: 0.3,
+        ..Scene::default()
+    };
+    scene.wormholes.push(Wormhole {
+        sheet: 1,
+        position: Vector3 {
+            x: 5.0,
+            y: -2.0,
+            z: 1.0,
+        },
+        other_position: Some(Vector3 {
+            x: -5.0,
+            y: 0.0,
+            z: 3.0,
+        }),
+        throat_size: 1.5,
+        throat_length: Some(1.0),
+    });
+    scene.camera.view_mode = ViewMode::Surface;
+    scene.camera.samples_per_pixel = 4;
+    scene.keyframes.push(Keyframe {
+        time: 2.0,
+        position: Vector4 {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            w: 4.0,
+        },
+        base_rotation: NoE4Rotor::rotate_xy(0.5),
+        xw_rotation: 0.25,
+        fov: 1.0,
+    });
+
+    let json = scene.to_json();
+    let loaded = Scene::from_json(&json).unwrap();
+
+    assert_eq!(loaded.camera.position, scene.camera.position);
+    assert_eq!(loaded.camera.view_mode, scene.camera.view_mode);
+    assert_eq!(
+        loaded.camera.samples_per_pixel,
+        scene.camera.samples_per_pixel
+    );
+    assert_eq!(loaded.sheets, scene.sheets);
+    assert_eq!(loaded.throat_length, scene.throat_length);
+    assert_eq!(loaded.wormholes, scene.wormholes);
+    assert_eq!(loaded.spheres, scene.spheres);
+    assert_eq!(loaded.gravity, scene.gravity);
+    assert_eq!(loaded.keyframes, scene.keyframes);
+    assert_eq!(loaded.lights, scene.lights);
+    assert_eq!(loaded.ambient, scene.ambient);
+    assert_eq!(loaded.tonemapping, scene.tonemapping);
+    assert_eq!(loaded.to_json(), json);
+}
+
+#[test]
+fn scene_files_need_a_version() {
+    assert!(matches!(
+        Scene::from_json("{}"),
+        Err(SceneError::MissingVersion)
+    ));
+    assert!(matches!(
+        Scene::from_json(r#"{"version":"1"}"#),
+        Err(SceneError::MissingVersion)
+    ));
+}
+
+#[test]
+fn newer_versions_are_unsupported() {
+    let json = format!(r#"{{"version":{}}}"#, SCENE_VERSION + 1);
+    assert!(matches!(
+        Scene::from_json(&json),
+        Err(SceneError::UnsupportedVersion(version)) if version == SCENE_VERSION as u64 + 1
+    ));
+}
+
+#[test]
+fn sheets_must_be_in_increasing_order() {
+    let scene = Scene {
+        sheets: vec![-4.0, 4.0, 2.0],
+        ..Default::default()
+    };
+    assert!(matches!(
+        Scene::from_json(&scene.to_json()),
+        Err(SceneError::UnsortedSheets)
+    ));
+
+    // sheets at the same w have no space between them for a wormhole
+    let scene = Scene {
+        sheets: vec![-4.0, -4.0],
+        ..Default::default()
+    };
+    assert!(matches!(
+        Scene::from_json(&scene.to_json()),
+        Err(SceneError::UnsortedSheets)
+    ));
+}
+
+#[test]
+fn old_versions_with_missing_fields_are_errors() {
+    // every migration step has to cope with whatever is missing, so that it is the deserialising
+    // afterwards that fails rather than the migration
+    for version in 0..=SCENE_VERSION {
+        let json = format!(r#"{{"version":{version}}}"#);
+        assert!(
+            matches!(Scene::from_json(&json), Err(SceneError::Json(_))),
+            "version {version}"
+        );
+    }
+}
+
+/// Each fixture is the default scene as it was saved by the version that introduced its format
+#[test]
+fn old_versions_are_migrated() {
+    let camera = Camera::default();
+    let sphere = Sphere::default();
+    for version in 1..SCENE_VERSION {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("scenes")
+            .join(format!("v{version}.json"));
+        let scene = Scene::load(&path).unwrap_or_else(|error| panic!("version {version}: {error}"));
+
+        assert_eq!(scene.sheets, [-4.0, 4.0], "version {version}");
+        assert_eq!(scene.throat_length, 4.0, "version {version}");
+        assert_eq!(scene.wormholes.len(), 1, "version {version}");
+        assert_eq!(scene.wormholes[0].sheet, 0, "version {version}");
+        assert_eq!(scene.spheres.len(), 1, "version {version}");
+        assert_eq!(scene.spheres[0].radius, sphere.radius, "version {version}");
+        assert_eq!(scene.spheres[0].color, sphere.color, "version {version}");
+        assert_eq!(
+            scene.camera.view_mode, camera.view_mode,
+            "version {version}"
+        );
+        assert_eq!(scene.camera.movement, camera.movement, "version {version}");
+        assert_eq!(
+            scene.camera.collision, camera.collision,
+            "version {version}"
+        );
+        assert_eq!(
+            scene.camera.samples_per_pixel, camera.samples_per_pixel,
+            "version {version}"
+        );
+        assert!(scene.bookmarks.is_empty(), "version {version}");
+        assert!(scene.keyframes.is_empty(), "version {version}");
+        assert_eq!(scene.lights, [Light::default()], "version {version}");
+        assert_eq!(
+            scene.tonemapping,
+            Tonemapping::default(),
+            "version {version}"
+        );
+    }
+}
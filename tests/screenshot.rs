@@ -0,0 +1,126 @@
+use raytracing::{
+    cpu::Image,
+    scene::Scene,
+    screenshot::{self, TILE_SIZE, Tile, padded_bytes_per_row},
+};
+
+#[test]
+fn tiles_cover_the_image_once() {
+    let tiles = screenshot::tiles(TILE_SIZE * 2 + 3, TILE_SIZE - 1).collect::<Vec<_>>();
+    assert_eq!(
+        tiles,
+        [
+            Tile {
+                x: 0,
+                y: 0,
+                width: TILE_SIZE,
+                height: TILE_SIZE - 1,
+            },
+            Tile {
+                x: TILE_SIZE,
+                y: 0,
+                width: TILE_SIZE,
+                height: TILE_SIZE - 1,
+            },
+            Tile {
+                x: TILE_SIZE * 2,
+                y: 0,
+                width: 3,
+                height: TILE_SIZE - 1,
+            },
+        ]
+    );
+    assert_eq!(screenshot::tiles(0, 10).count(), 0);
+}
+
+#[test]
+fn pasted_tiles_are_clipped_to_the_image() {
+    let mut image = Image {
+        width: 3,
+        height: 2,
+        pixels: vec![[0; 4]; 6],
+    };
+    let tile = Image {
+        width: 2,
+        height: 2,
+        pixels: vec![[1; 4], [2; 4], [3; 4], [4; 4]],
+    };
+    image.paste(&tile, 0, 0);
+    image.paste(&tile, 2, 1);
+    assert_eq!(
+        image.pixels,
+        [[1; 4], [2; 4], [0; 4], [3; 4], [4; 4], [1; 4]]
+    );
+}
+
+#[test]
+fn sequence_frames_are_numbered_before_the_extension() {
+    assert_eq!(
+        screenshot::numbered_path("shots/flight.png", 7),
+        std::path::Path::new("shots/flight_00007.png")
+    );
+    assert_eq!(
+        screenshot::numbered_path("flight", 123),
+        std::path::Path::new("flight_00123")
+    );
+}
+
+#[test]
+fn rows_are_padded_to_the_copy_alignment() {
+    assert_eq!(padded_bytes_per_row(1), 256);
+    assert_eq!(padded_bytes_per_row(64), 256);
+    assert_eq!(padded_bytes_per_row(65), 512);
+}
+
+#[test]
+fn padding_is_removed_and_bgra_is_swapped() {
+    let bytes_per_row = padded_bytes_per_row(2) as usize;
+    let mut data = vec![0xff; bytes_per_row * 2];
+    data[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    data[bytes_per_row..bytes_per_row + 8].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+
+    let image = screenshot::image_from_padded(2, 2, false, &data);
+    assert_eq!(
+        image.pixels,
+        [
+            [1, 2, 3, 4],
+            [5, 6, 7, 8],
+            [9, 10, 11, 12],
+            [13, 14, 15, 16]
+        ]
+    );
+
+    let image = screenshot::image_from_padded(2, 2, true, &data);
+    assert_eq!(image.get(1, 1), [15, 14, 13, 16]);
+}
+
+#[test]
+fn scene_is_written_into_the_metadata() {
+    let mut scene = Scene::default();
+    scene.camera.fov = 1.25;
+    let image = Image {
+        width: 2,
+        height: 1,
+        pixels: vec![[255, 0, 0, 255], [0, 0, 255, 255]],
+    };
+
+    let path = std::env::temp_dir().join(format!("screenshot-{}.png", std::process::id()));
+    screenshot::save(&image, &scene, &path).unwrap();
+    assert_eq!(Image::read_png(&path).unwrap(), image);
+
+    let reader = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(&path).unwrap()))
+        .read_info()
+        .unwrap();
+    let text = |keyword: &str| {
+        let chunk = reader
+            .info()
+            .utf8_text
+            .iter()
+            .find(|chunk| chunk.keyword == keyword)
+            .unwrap();
+        chunk.get_text().unwrap()
+    };
+    assert_eq!(Scene::from_json(&text("Scene")).unwrap().camera.fov, 1.25);
+    assert!(text("Camera").contains("\"fov\": 1.25"));
+    std::fs::remove_file(path).unwrap();
+}
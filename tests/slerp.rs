@@ -0,0 +1,86 @@
+use math::{NoE4Rotor, Rotor, Vector4};
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+fn assert_same_rotation(a: Rotor, b: Rotor) {
+    for (a, b) in [
+        (a.x(), b.x()),
+        (a.y(), b.y()),
+        (a.z(), b.z()),
+        (a.w(), b.w()),
+    ] {
+        assert!((a - b).magnitude() < 0.001, "{a:?} != {b:?}");
+    }
+}
+
+fn some_rotation() -> Rotor {
+    Rotor::rotate_xz(0.7)
+        .then(Rotor::rotate_zw(0.3))
+        .then(Rotor::rotate_xy(-2.0))
+        .then(Rotor::from_to_vector(
+            Vector4 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+            Vector4 {
+                x: 0.5,
+                y: -0.5,
+                z: 0.5,
+                w: 0.5,
+            },
+        ))
+}
+
+#[test]
+fn no_e4_rotor_turns_the_short_way() {
+    let to = NoE4Rotor::rotate_xy(1.0);
+    let halfway = NoE4Rotor::identity().slerp(to, 0.25);
+    let expected = NoE4Rotor::rotate_xy(0.25);
+    assert!((halfway.x() - expected.x()).magnitude() < 0.001);
+
+    // three quarters of a turn one way is a quarter the other
+    let to = NoE4Rotor::rotate_xz(TAU * 0.75);
+    let halfway = NoE4Rotor::identity().slerp(to, 0.5);
+    let expected = NoE4Rotor::rotate_xz(-TAU * 0.125);
+    assert!((halfway.x() - expected.x()).magnitude() < 0.001);
+}
+
+#[test]
+fn double_rotations_turn_both_planes_evenly() {
+    let to = Rotor::rotate_xy(1.2).then(Rotor::rotate_zw(0.4));
+    assert_same_rotation(
+        Rotor::identity().slerp(to, 0.5),
+        Rotor::rotate_xy(0.6).then(Rotor::rotate_zw(0.2)),
+    );
+    assert_same_rotation(
+        Rotor::identity().slerp(to, 0.25),
+        Rotor::rotate_xy(0.3).then(Rotor::rotate_zw(0.1)),
+    );
+}
+
+#[test]
+fn ends_at_both_rotations() {
+    let from = Rotor::rotate_yw(FRAC_PI_2).then(Rotor::rotate_xz(-0.4));
+    let to = some_rotation();
+    assert_same_rotation(from.slerp(to, 0.0), from);
+    assert_same_rotation(from.slerp(to, 1.0), to);
+}
+
+#[test]
+fn follows_a_geodesic() {
+    // halfway to a rotation, done twice, is the whole rotation
+    let rotation = some_rotation();
+    let halfway = Rotor::identity().slerp(rotation, 0.5);
+    assert_same_rotation(halfway.then(halfway), rotation);
+
+    // and the path between any two rotations is the same as from the identity to the difference
+    let from = Rotor::rotate_yw(FRAC_PI_2).then(Rotor::rotate_xz(-0.4));
+    let difference = from.reverse().then(rotation);
+    for t in [0.2, 0.5, 0.9] {
+        assert_same_rotation(
+            from.slerp(rotation, t),
+            from.then(Rotor::identity().slerp(difference, t)),
+        );
+    }
+}